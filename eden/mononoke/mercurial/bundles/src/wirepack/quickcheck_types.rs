@@ -66,7 +66,7 @@ impl Arbitrary for DataEntry {
             (delta_base, Delta::arbitrary(g))
         };
 
-        // 50% chance of having metadata (i.e. being v2)
+        // 50% chance of having a metadata block at all
         let metadata = if bool::arbitrary(g) {
             // 50% chance of flags being present
             let flags = if bool::arbitrary(g) { Some(1) } else { None };
@@ -13,8 +13,12 @@ use std::str::FromStr;
 
 use anyhow::Error;
 use anyhow::format_err;
+use blobstore::Loadable;
 use bonsai_git_mapping::BonsaiGitMapping;
+use bonsai_git_mapping::BonsaiGitMappingEntry;
+use bonsai_git_mapping::BonsaiGitMappingRef;
 use bonsai_hg_mapping::BonsaiHgMapping;
+use bonsai_hg_mapping::BonsaiHgMappingEntry;
 use bonsai_hg_mapping::BonsaiHgMappingRef;
 use bonsai_tag_mapping::BonsaiTagMapping;
 use bookmarks::BookmarkKey;
@@ -40,16 +44,21 @@ use futures::stream;
 use futures::stream::StreamExt;
 use futures::stream::TryStreamExt;
 use manifest::ManifestOps;
+use manifest::PathOrPrefix;
 use maplit::btreemap;
 use mercurial_types::HgChangesetId;
+use mercurial_types::HgNodeHash;
+use mercurial_types::NULL_HASH;
 use metaconfig_types::RepoConfig;
 use mononoke_types::BonsaiChangesetMut;
 use mononoke_types::ChangesetId;
+use mononoke_types::ContentId;
 use mononoke_types::DateTime;
 use mononoke_types::FileChange;
 use mononoke_types::FileType;
 use mononoke_types::GitLfs;
 use mononoke_types::NonRootMPath;
+use mononoke_types::hash::GitSha1;
 use repo_blobstore::RepoBlobstore;
 use repo_blobstore::RepoBlobstoreArc;
 use repo_derived_data::RepoDerivedData;
@@ -60,7 +69,8 @@ use repo_identity::RepoIdentityRef;
 pub mod drawdag;
 pub mod random;
 
-pub trait Repo = BonsaiHgMappingRef
+pub trait Repo = BonsaiGitMappingRef
+    + BonsaiHgMappingRef
     + BookmarksRef
     + CommitGraphRef
     + CommitGraphWriterRef
@@ -153,6 +163,57 @@ pub async fn list_working_copy_with_types(
     ctx: &CoreContext,
     repo: &impl Repo,
     cs_id: ChangesetId,
+) -> Result<HashMap<NonRootMPath, (Bytes, FileType)>, Error> {
+    list_working_copy_with_types_impl(ctx, repo, cs_id, None).await
+}
+
+/// Bounded, TTL'd cache of fully-assembled file content keyed by `ContentId`.
+/// Pass the same `ContentCache` to `list_working_copy_with_cache` across
+/// several changesets (e.g. enumerating a `drawdag`-generated history) to
+/// turn repeated, overlapping manifest walks from O(total leaves) fetches
+/// into O(distinct contents). Opt-in: `list_working_copy_with_types` doesn't
+/// use one, since most call sites only ever look at a single changeset.
+pub struct ContentCache {
+    cache: moka::sync::Cache<ContentId, Bytes>,
+}
+
+impl ContentCache {
+    /// `capacity` bounds the number of distinct contents held at once;
+    /// `ttl` bounds how long an entry survives after it was inserted,
+    /// however often it is read in the meantime.
+    pub fn new(capacity: u64, ttl: std::time::Duration) -> Self {
+        Self {
+            cache: moka::sync::Cache::builder()
+                .max_capacity(capacity)
+                .time_to_live(ttl)
+                .build(),
+        }
+    }
+}
+
+impl Default for ContentCache {
+    fn default() -> Self {
+        Self::new(10_000, std::time::Duration::from_secs(300))
+    }
+}
+
+/// Like `list_working_copy_with_types`, but memoizes fetched file content by
+/// `ContentId` in `cache` so that identical blobs shared across overlapping
+/// working copies are only fetched once.
+pub async fn list_working_copy_with_cache(
+    ctx: &CoreContext,
+    repo: &impl Repo,
+    cs_id: ChangesetId,
+    cache: &ContentCache,
+) -> Result<HashMap<NonRootMPath, (Bytes, FileType)>, Error> {
+    list_working_copy_with_types_impl(ctx, repo, cs_id, Some(cache)).await
+}
+
+async fn list_working_copy_with_types_impl(
+    ctx: &CoreContext,
+    repo: &impl Repo,
+    cs_id: ChangesetId,
+    cache: Option<&ContentCache>,
 ) -> Result<HashMap<NonRootMPath, (Bytes, FileType)>, Error> {
     if let Ok(true) = justknobs::eval(
         "scm/mononoke:derived_data_use_content_manifests",
@@ -181,6 +242,12 @@ pub async fn list_working_copy_with_types(
             .right_stream()
     }
     .map_ok(|(path, content_id, file_type)| async move {
+        if let Some(cache) = cache {
+            if let Some(bytes) = cache.cache.get(&content_id) {
+                return Ok((path, (bytes, file_type)));
+            }
+        }
+
         let maybe_content = filestore::fetch(
             repo.repo_blobstore(),
             ctx.clone(),
@@ -202,14 +269,606 @@ pub async fn list_working_copy_with_types(
                 bytes.extend_from_slice(&new_bytes);
                 future::ready(Ok(bytes))
             })
+            .await?
+            .freeze();
+
+        if let Some(cache) = cache {
+            cache.cache.insert(content_id, bytes.clone());
+        }
+
+        Ok((path, (bytes, file_type)))
+    })
+    .try_buffer_unordered(100)
+    .try_collect()
+    .await
+}
+
+/// A path matcher for `list_working_copy_matching`: either an exact subtree
+/// prefix, which is pruned directly out of the manifest walk (non-matching
+/// subtrees are never descended into, let alone materialized), or a glob
+/// pattern matched against each leaf's full path once reached. Globs can
+/// match anywhere in the tree, so on their own they can't prune the walk --
+/// if any matcher is a `Glob`, the walk falls back to visiting everything
+/// and filtering the results.
+pub enum PathMatcher {
+    Prefix(NonRootMPath),
+    Glob(String),
+}
+
+/// Dependency-free glob match: `*` matches any run of characters (including
+/// `/`), everything else matches literally.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    fn go(pattern: &[u8], path: &[u8]) -> bool {
+        match pattern.first() {
+            None => path.is_empty(),
+            Some(b'*') => go(&pattern[1..], path) || (!path.is_empty() && go(pattern, &path[1..])),
+            Some(p) => path.first() == Some(p) && go(&pattern[1..], &path[1..]),
+        }
+    }
+    go(pattern.as_bytes(), path.as_bytes())
+}
+
+fn path_under_prefix(path: &NonRootMPath, prefix: &NonRootMPath) -> bool {
+    let path = path.to_string();
+    let prefix = prefix.to_string();
+    path == prefix || path.starts_with(&format!("{}/", prefix))
+}
+
+/// Like `list_working_copy_with_types`, but only materializes leaves that
+/// match one of `matchers`, short-circuiting the manifest walk on
+/// non-matching subtrees rather than listing everything and filtering
+/// afterward. Lets tests over wide trees assert on one directory without
+/// paying to fetch and decode the entire working copy.
+pub async fn list_working_copy_matching(
+    ctx: &CoreContext,
+    repo: &impl Repo,
+    cs_id: ChangesetId,
+    matchers: &[PathMatcher],
+) -> Result<HashMap<NonRootMPath, (Bytes, FileType)>, Error> {
+    let paths_or_prefixes: Vec<PathOrPrefix> = matchers
+        .iter()
+        .map(|matcher| match matcher {
+            PathMatcher::Prefix(path) => PathOrPrefix::Prefix(Some(path.clone())),
+            // Can't prune by glob alone -- walk the whole tree and let the
+            // `try_filter` below pick out what actually matches.
+            PathMatcher::Glob(_) => PathOrPrefix::Prefix(None),
+        })
+        .collect();
+
+    if let Ok(true) = justknobs::eval(
+        "scm/mononoke:derived_data_use_content_manifests",
+        None,
+        None,
+    ) {
+        let root = repo
+            .repo_derived_data()
+            .derive::<RootContentManifestId>(ctx, cs_id)
+            .await?;
+
+        root.into_content_manifest_id()
+            .find_entries(ctx.clone(), repo.repo_blobstore_arc(), paths_or_prefixes)
+            .try_filter_map(|(path, entry)| {
+                future::ready(Ok(entry
+                    .into_leaf()
+                    .map(|file| (path, file.content_id, file.file_type))))
+            })
+            .left_stream()
+    } else {
+        let root_fsnode_id = repo
+            .repo_derived_data()
+            .derive::<RootFsnodeId>(ctx, cs_id)
             .await?;
-        Ok((path, (bytes.freeze(), file_type)))
+
+        root_fsnode_id
+            .fsnode_id()
+            .find_entries(ctx.clone(), repo.repo_blobstore_arc(), paths_or_prefixes)
+            .try_filter_map(|(path, entry)| {
+                future::ready(Ok(entry
+                    .into_leaf()
+                    .map(|file| (path, *file.content_id(), *file.file_type()))))
+            })
+            .right_stream()
+    }
+    .try_filter(|(path, _content_id, _file_type)| {
+        future::ready(matchers.iter().any(|matcher| match matcher {
+            PathMatcher::Prefix(prefix) => path_under_prefix(path, prefix),
+            PathMatcher::Glob(pattern) => glob_match(pattern, &path.to_string()),
+        }))
+    })
+    .map_ok(|(path, content_id, file_type)| async move {
+        let maybe_content = filestore::fetch(
+            repo.repo_blobstore(),
+            ctx.clone(),
+            &FetchKey::Canonical(content_id),
+        )
+        .await?;
+        let s = match maybe_content {
+            Some(s) => s,
+            None => {
+                return Err(format_err!(
+                    "cannot fetch content for {} {}",
+                    path,
+                    content_id
+                ));
+            }
+        };
+        let bytes = s
+            .try_fold(BytesMut::new(), |mut bytes, new_bytes| {
+                bytes.extend_from_slice(&new_bytes);
+                future::ready(Ok(bytes))
+            })
+            .await?
+            .freeze();
+        Ok((path, (bytes, file_type)))
     })
     .try_buffer_unordered(100)
     .try_collect()
     .await
 }
 
+async fn list_leaf_metadata(
+    ctx: &CoreContext,
+    repo: &impl Repo,
+    cs_id: ChangesetId,
+) -> Result<HashMap<NonRootMPath, (ContentId, FileType)>, Error> {
+    if let Ok(true) = justknobs::eval(
+        "scm/mononoke:derived_data_use_content_manifests",
+        None,
+        None,
+    ) {
+        let root = repo
+            .repo_derived_data()
+            .derive::<RootContentManifestId>(ctx, cs_id)
+            .await?;
+
+        root.into_content_manifest_id()
+            .list_leaf_entries(ctx.clone(), repo.repo_blobstore_arc())
+            .map_ok(|(path, file)| (path, (file.content_id, file.file_type)))
+            .try_collect()
+            .await
+    } else {
+        let root_fsnode_id = repo
+            .repo_derived_data()
+            .derive::<RootFsnodeId>(ctx, cs_id)
+            .await?;
+
+        root_fsnode_id
+            .fsnode_id()
+            .list_leaf_entries(ctx.clone(), repo.repo_blobstore_arc())
+            .map_ok(|(path, file)| (path, (*file.content_id(), *file.file_type())))
+            .try_collect()
+            .await
+    }
+}
+
+async fn fetch_blob_bytes(
+    ctx: &CoreContext,
+    repo: &impl Repo,
+    content_id: ContentId,
+) -> Result<Bytes, Error> {
+    let maybe_content = filestore::fetch(
+        repo.repo_blobstore(),
+        ctx.clone(),
+        &FetchKey::Canonical(content_id),
+    )
+    .await?;
+    let s = maybe_content.ok_or_else(|| format_err!("cannot fetch content for {}", content_id))?;
+    let bytes = s
+        .try_fold(BytesMut::new(), |mut bytes, new_bytes| {
+            bytes.extend_from_slice(&new_bytes);
+            future::ready(Ok(bytes))
+        })
+        .await?;
+    Ok(bytes.freeze())
+}
+
+/// Whether a path was added, deleted, or modified between `changeset_stats`'s
+/// base and target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileStatus {
+    Added,
+    Deleted,
+    Modified,
+}
+
+/// One path's entry in a `changeset_stats` summary.
+#[derive(Clone, Debug)]
+pub struct FileStat {
+    pub path: NonRootMPath,
+    pub status: FileStatus,
+    pub copy_from: Option<NonRootMPath>,
+    pub insertions: Option<usize>,
+    pub deletions: Option<usize>,
+}
+
+/// Summarizes the changes between `base` and `target` in the style of
+/// `git diff --stat`: per-file added/deleted/modified status, copy source
+/// when recorded against `target`'s own bonsai file changes, and -- only
+/// when `count_lines` is set -- insertion/deletion counts for text files.
+/// Status classification relies solely on manifest leaf metadata (`ContentId`,
+/// `FileType`); blob content is only fetched for a path whose line counts are
+/// actually requested, so a pure "what changed" summary never materializes
+/// file content.
+pub async fn changeset_stats(
+    ctx: &CoreContext,
+    repo: &impl Repo,
+    base: impl Into<CommitIdentifier>,
+    target: impl Into<CommitIdentifier>,
+    count_lines: bool,
+) -> Result<Vec<FileStat>, Error> {
+    let base_cs_id = resolve_cs_id(ctx, repo, base).await?;
+    let target_cs_id = resolve_cs_id(ctx, repo, target).await?;
+
+    let base_meta = list_leaf_metadata(ctx, repo, base_cs_id).await?;
+    let target_meta = list_leaf_metadata(ctx, repo, target_cs_id).await?;
+
+    // Copy-from info is only recorded against a changeset's actual parents,
+    // so it's only meaningful here when `base` is one of `target`'s parents.
+    let mut copy_froms = HashMap::new();
+    let target_bcs = target_cs_id.load(ctx, repo.repo_blobstore()).await?;
+    if target_bcs.parents().any(|parent| parent == base_cs_id) {
+        for (fc_path, file_change) in target_bcs.simplified_file_changes() {
+            if let Some(tc) = file_change {
+                if let Some((from_path, _)) = tc.copy_from() {
+                    copy_froms.insert(fc_path.clone(), from_path.clone());
+                }
+            }
+        }
+    }
+
+    let mut paths: Vec<&NonRootMPath> = base_meta.keys().chain(target_meta.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut stats = Vec::new();
+    for path in paths {
+        let old = base_meta.get(path);
+        let new = target_meta.get(path);
+        let status = match (old, new) {
+            (None, Some(_)) => FileStatus::Added,
+            (Some(_), None) => FileStatus::Deleted,
+            (Some(old), Some(new)) if old == new => continue,
+            (Some(_), Some(_)) => FileStatus::Modified,
+            (None, None) => continue,
+        };
+
+        let (insertions, deletions) = if count_lines {
+            let old_bytes = match old {
+                Some((content_id, _)) => Some(fetch_blob_bytes(ctx, repo, *content_id).await?),
+                None => None,
+            };
+            let new_bytes = match new {
+                Some((content_id, _)) => Some(fetch_blob_bytes(ctx, repo, *content_id).await?),
+                None => None,
+            };
+            let is_binary = old_bytes.as_deref().is_some_and(looks_binary)
+                || new_bytes.as_deref().is_some_and(looks_binary);
+
+            if is_binary {
+                (None, None)
+            } else {
+                let old_text = old_bytes
+                    .as_deref()
+                    .map(String::from_utf8_lossy)
+                    .unwrap_or_default();
+                let new_text = new_bytes
+                    .as_deref()
+                    .map(String::from_utf8_lossy)
+                    .unwrap_or_default();
+                let old_lines: Vec<&str> = old_text.lines().collect();
+                let new_lines: Vec<&str> = new_text.lines().collect();
+
+                let mut insertions = 0usize;
+                let mut deletions = 0usize;
+                for op in lcs_diff(&old_lines, &new_lines) {
+                    match op {
+                        DiffOp::Insert(_) => insertions += 1,
+                        DiffOp::Delete(_) => deletions += 1,
+                        DiffOp::Equal(_, _) => {}
+                    }
+                }
+                (Some(insertions), Some(deletions))
+            }
+        } else {
+            (None, None)
+        };
+
+        stats.push(FileStat {
+            path: path.clone(),
+            status,
+            copy_from: copy_froms.get(path).cloned(),
+            insertions,
+            deletions,
+        });
+    }
+
+    Ok(stats)
+}
+
+/// How a path differs between a `diff_changesets` base and target working copy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ChangeKind {
+    Added,
+    Deleted,
+    Modified,
+}
+
+/// Git's mode string for a tracked file's `FileType`. Only the three kinds
+/// `diff_changesets` can encounter in a working-copy listing are meaningful
+/// here; anything else falls back to the plain-file mode rather than failing
+/// to match.
+fn diff_file_mode(file_type: FileType) -> &'static str {
+    match file_type {
+        FileType::Regular => "100644",
+        FileType::Executable => "100755",
+        FileType::Symlink => "120000",
+        _ => "100644",
+    }
+}
+
+fn looks_binary(content: &[u8]) -> bool {
+    content.contains(&0)
+}
+
+enum DiffOp {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Line-level LCS diff between `old` and `new`. Quadratic in the number of
+/// lines, which is fine for the small fixtures tests diff -- not meant for
+/// huge files.
+fn lcs_diff(old: &[&str], new: &[&str]) -> Vec<DiffOp> {
+    let n = old.len();
+    let m = new.len();
+    let mut dp = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            dp[i][j] = if old[i] == new[j] {
+                dp[i + 1][j + 1] + 1
+            } else {
+                dp[i + 1][j].max(dp[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            ops.push(DiffOp::Equal(i, j));
+            i += 1;
+            j += 1;
+        } else if dp[i + 1][j] >= dp[i][j + 1] {
+            ops.push(DiffOp::Delete(i));
+            i += 1;
+        } else {
+            ops.push(DiffOp::Insert(j));
+            j += 1;
+        }
+    }
+    while i < n {
+        ops.push(DiffOp::Delete(i));
+        i += 1;
+    }
+    while j < m {
+        ops.push(DiffOp::Insert(j));
+        j += 1;
+    }
+    ops
+}
+
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Renders `ops` as `@@ -a,b +c,d @@` unified hunks, keeping up to
+/// `DIFF_CONTEXT_LINES` lines of unchanged context around each change and
+/// merging change blocks whose context would otherwise overlap.
+fn format_hunks(old: &[&str], new: &[&str], ops: &[DiffOp]) -> String {
+    let mut old_pos_before = Vec::with_capacity(ops.len());
+    let mut new_pos_before = Vec::with_capacity(ops.len());
+    let (mut old_cursor, mut new_cursor) = (0usize, 0usize);
+    for op in ops {
+        old_pos_before.push(old_cursor);
+        new_pos_before.push(new_cursor);
+        match op {
+            DiffOp::Equal(_, _) => {
+                old_cursor += 1;
+                new_cursor += 1;
+            }
+            DiffOp::Delete(_) => old_cursor += 1,
+            DiffOp::Insert(_) => new_cursor += 1,
+        }
+    }
+
+    let mut keep = vec![false; ops.len()];
+    for (i, op) in ops.iter().enumerate() {
+        if !matches!(op, DiffOp::Equal(_, _)) {
+            let lo = i.saturating_sub(DIFF_CONTEXT_LINES);
+            let hi = (i + DIFF_CONTEXT_LINES + 1).min(ops.len());
+            keep[lo..hi].fill(true);
+        }
+    }
+
+    let mut out = String::new();
+    let mut idx = 0;
+    while idx < ops.len() {
+        if !keep[idx] {
+            idx += 1;
+            continue;
+        }
+        let lo = idx;
+        let mut hi = idx;
+        while hi < ops.len() && keep[hi] {
+            hi += 1;
+        }
+
+        let old_start = old_pos_before[lo];
+        let new_start = new_pos_before[lo];
+        let old_end = if hi < ops.len() {
+            old_pos_before[hi]
+        } else {
+            old_cursor
+        };
+        let new_end = if hi < ops.len() {
+            new_pos_before[hi]
+        } else {
+            new_cursor
+        };
+        let old_count = old_end - old_start;
+        let new_count = new_end - new_start;
+
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_count == 0 { old_start } else { old_start + 1 },
+            old_count,
+            if new_count == 0 { new_start } else { new_start + 1 },
+            new_count,
+        ));
+        for op in &ops[lo..hi] {
+            match op {
+                DiffOp::Equal(i, _) => out.push_str(&format!(" {}\n", old[*i])),
+                DiffOp::Delete(i) => out.push_str(&format!("-{}\n", old[*i])),
+                DiffOp::Insert(j) => out.push_str(&format!("+{}\n", new[*j])),
+            }
+        }
+
+        idx = hi;
+    }
+    out
+}
+
+/// Diffs the working copies of `base` and `target`, producing a git-style
+/// unified diff so tests can assert on the textual changes a commit
+/// introduces instead of manually comparing `list_working_copy_with_types`
+/// maps. With `format_patch` set, wraps the diff in a `git format-patch`
+/// style single-message mbox, using `target`'s author/date/message for the
+/// envelope.
+pub async fn diff_changesets(
+    ctx: &CoreContext,
+    repo: &impl Repo,
+    base: impl Into<CommitIdentifier>,
+    target: impl Into<CommitIdentifier>,
+    format_patch: bool,
+) -> Result<Bytes, Error> {
+    let base_cs_id = resolve_cs_id(ctx, repo, base).await?;
+    let target_cs_id = resolve_cs_id(ctx, repo, target).await?;
+
+    let base_wc = list_working_copy_with_types(ctx, repo, base_cs_id).await?;
+    let target_wc = list_working_copy_with_types(ctx, repo, target_cs_id).await?;
+
+    let mut paths: Vec<&NonRootMPath> = base_wc.keys().chain(target_wc.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut body = String::new();
+    let mut files_changed = 0usize;
+    let mut insertions = 0usize;
+    let mut deletions = 0usize;
+
+    for path in paths {
+        let old = base_wc.get(path);
+        let new = target_wc.get(path);
+        let (kind, old_type, new_type) = match (old, new) {
+            (None, Some((_, new_type))) => (ChangeKind::Added, *new_type, *new_type),
+            (Some((_, old_type)), None) => (ChangeKind::Deleted, *old_type, *old_type),
+            (Some((old_bytes, old_type)), Some((new_bytes, new_type))) => {
+                if old_bytes == new_bytes && old_type == new_type {
+                    continue;
+                }
+                (ChangeKind::Modified, *old_type, *new_type)
+            }
+            (None, None) => continue,
+        };
+
+        files_changed += 1;
+        body.push_str(&format!("diff --git a/{path} b/{path}\n"));
+        match kind {
+            ChangeKind::Added => {
+                body.push_str(&format!("new file mode {}\n", diff_file_mode(new_type)));
+            }
+            ChangeKind::Deleted => {
+                body.push_str(&format!("deleted file mode {}\n", diff_file_mode(old_type)));
+            }
+            ChangeKind::Modified if old_type != new_type => {
+                body.push_str(&format!(
+                    "old mode {}\nnew mode {}\n",
+                    diff_file_mode(old_type),
+                    diff_file_mode(new_type),
+                ));
+            }
+            ChangeKind::Modified => {}
+        }
+
+        let empty = Bytes::new();
+        let old_bytes = old.map_or(&empty, |(bytes, _)| bytes);
+        let new_bytes = new.map_or(&empty, |(bytes, _)| bytes);
+
+        if looks_binary(old_bytes) || looks_binary(new_bytes) {
+            body.push_str("Binary files differ\n");
+            continue;
+        }
+
+        let old_text = String::from_utf8_lossy(old_bytes);
+        let new_text = String::from_utf8_lossy(new_bytes);
+        let old_lines: Vec<&str> = old_text.lines().collect();
+        let new_lines: Vec<&str> = new_text.lines().collect();
+
+        body.push_str(&format!(
+            "--- {}\n",
+            if kind == ChangeKind::Added {
+                "/dev/null".to_string()
+            } else {
+                format!("a/{path}")
+            }
+        ));
+        body.push_str(&format!(
+            "+++ {}\n",
+            if kind == ChangeKind::Deleted {
+                "/dev/null".to_string()
+            } else {
+                format!("b/{path}")
+            }
+        ));
+
+        let ops = lcs_diff(&old_lines, &new_lines);
+        for op in &ops {
+            match op {
+                DiffOp::Delete(_) => deletions += 1,
+                DiffOp::Insert(_) => insertions += 1,
+                DiffOp::Equal(_, _) => {}
+            }
+        }
+        body.push_str(&format_hunks(&old_lines, &new_lines, &ops));
+    }
+
+    let mut out = String::new();
+    if format_patch {
+        let bcs = target_cs_id.load(ctx, repo.repo_blobstore()).await?;
+        let subject = bcs.message().lines().next().unwrap_or("");
+        out.push_str(&format!("From {} Mon Sep 17 00:00:00 2001\n", target_cs_id));
+        out.push_str(&format!("From: {}\n", bcs.author()));
+        out.push_str(&format!("Date: {}\n", bcs.author_date()));
+        out.push_str(&format!("Subject: [PATCH] {}\n\n", subject));
+    }
+
+    out.push_str(&body);
+
+    if format_patch {
+        out.push_str(&format!(
+            "---\n {} file{} changed, {} insertion{}(+), {} deletion{}(-)\n-- \n",
+            files_changed,
+            if files_changed == 1 { "" } else { "s" },
+            insertions,
+            if insertions == 1 { "" } else { "s" },
+            deletions,
+            if deletions == 1 { "" } else { "s" },
+        ));
+    }
+
+    Ok(Bytes::from(out))
+}
+
 /// Helper to create bonsai changesets in a repo
 pub struct CreateCommitContext<'a, R: Repo> {
     ctx: &'a CoreContext,
@@ -472,6 +1131,194 @@ impl<'a, R: Repo> CreateCommitContext<'a, R> {
         save_changesets(ctx, repo, vec![bcs]).await?;
         Ok(bcs_id)
     }
+
+    /// Applies a unified-diff/git-patch blob (as produced by `diff_changesets`)
+    /// against `parent`'s working copy, translating the result into
+    /// `add_file`/`delete_file`/`add_file_with_copy_info` calls. Lets test
+    /// authors reproduce a captured real-world change instead of hand-encoding
+    /// every `FileChange`. Doesn't support binary diffs, and a rename combined
+    /// with content changes keeps the renamed-to content as the pre-rename
+    /// content with hunks applied -- there's no attempt to diff a similarity
+    /// index.
+    pub async fn apply_patch(
+        mut self,
+        parent: impl Into<CommitIdentifier>,
+        patch: impl Into<Vec<u8>>,
+    ) -> Result<Self, Error> {
+        let parent_ident = parent.into();
+        let parent_cs_id = resolve_cs_id(self.ctx, self.repo, parent_ident).await?;
+        let base_wc = list_working_copy(self.ctx, self.repo, parent_cs_id).await?;
+
+        let patch = patch.into();
+        let patch_text = String::from_utf8(patch)
+            .map_err(|_| format_err!("apply_patch only supports UTF-8 patches"))?;
+
+        for block in split_diff_blocks(&patch_text) {
+            self = apply_diff_block(self, &base_wc, parent_cs_id, &block)?;
+        }
+        Ok(self)
+    }
+}
+
+/// Splits a multi-file patch into the lines belonging to each `diff --git`
+/// block (the header line itself included).
+fn split_diff_blocks(patch_text: &str) -> Vec<Vec<&str>> {
+    let mut blocks: Vec<Vec<&str>> = Vec::new();
+    for line in patch_text.lines() {
+        if line.starts_with("diff --git ") {
+            blocks.push(vec![line]);
+        } else if let Some(block) = blocks.last_mut() {
+            block.push(line);
+        }
+        // Lines before the first "diff --git" (e.g. a format-patch mbox
+        // preamble) aren't part of any file's diff and are dropped.
+    }
+    blocks
+}
+
+fn parse_diff_git_header(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix("diff --git a/")?;
+    let sep = rest.find(" b/")?;
+    Some((&rest[..sep], &rest[sep + 3..]))
+}
+
+/// 0-based position in the pre-image that a hunk's `@@ -start,count +.. @@`
+/// header points at.
+fn parse_hunk_old_start(header: &str) -> Option<usize> {
+    let inner = header.strip_prefix("@@ -")?;
+    let old_part = inner.split(' ').next()?;
+    let (start, count) = match old_part.split_once(',') {
+        Some((start, count)) => (start, count),
+        None => (old_part, "1"),
+    };
+    let start: usize = start.parse().ok()?;
+    let count: usize = count.parse().ok()?;
+    // A zero-length pre-image range points at the line *before* which the
+    // hunk applies, using the position as-is rather than `start - 1`.
+    Some(if count == 0 { start } else { start - 1 })
+}
+
+/// Replays the hunks in `lines` (starting at the first `@@` header) against
+/// `base_lines`, returning the resulting file content as whole lines.
+fn apply_hunks(base_lines: &[&str], lines: &[&str]) -> Result<Vec<String>, Error> {
+    let mut result = Vec::new();
+    let mut pos = 0usize;
+    let mut i = 0;
+    while i < lines.len() {
+        if !lines[i].starts_with("@@ ") {
+            i += 1;
+            continue;
+        }
+        let old_start = parse_hunk_old_start(lines[i])
+            .ok_or_else(|| format_err!("malformed hunk header: {}", lines[i]))?;
+        while pos < old_start {
+            result.push(base_lines.get(pos).copied().unwrap_or("").to_string());
+            pos += 1;
+        }
+        i += 1;
+
+        while i < lines.len() && !lines[i].starts_with("@@ ") {
+            let line = lines[i];
+            if let Some(rest) = line.strip_prefix(' ') {
+                if base_lines.get(pos) != Some(&rest) {
+                    return Err(format_err!(
+                        "context line mismatch applying hunk: expected {:?}, found {:?}",
+                        base_lines.get(pos),
+                        rest
+                    ));
+                }
+                result.push(rest.to_string());
+                pos += 1;
+            } else if let Some(rest) = line.strip_prefix('-') {
+                if base_lines.get(pos) != Some(&rest) {
+                    return Err(format_err!(
+                        "delete line mismatch applying hunk: expected {:?}, found {:?}",
+                        base_lines.get(pos),
+                        rest
+                    ));
+                }
+                pos += 1;
+            } else if let Some(rest) = line.strip_prefix('+') {
+                result.push(rest.to_string());
+            } else {
+                // Blank line separating this file's diff from the next, or
+                // trailing patch footer (e.g. `format_patch`'s `---` summary).
+                break;
+            }
+            i += 1;
+        }
+    }
+    while pos < base_lines.len() {
+        result.push(base_lines[pos].to_string());
+        pos += 1;
+    }
+    Ok(result)
+}
+
+fn apply_diff_block<'a, R: Repo>(
+    mut ctx: CreateCommitContext<'a, R>,
+    base_wc: &HashMap<NonRootMPath, Bytes>,
+    parent_cs_id: ChangesetId,
+    block: &[&str],
+) -> Result<CreateCommitContext<'a, R>, Error> {
+    let (old_path, new_path) = parse_diff_git_header(block[0])
+        .ok_or_else(|| format_err!("malformed diff --git header: {}", block[0]))?;
+
+    let mut deleted = false;
+    let mut rename_from: Option<&str> = None;
+    let mut rename_to: Option<&str> = None;
+    let mut hunks_start = None;
+    for (i, line) in block.iter().enumerate().skip(1) {
+        if line.starts_with("deleted file mode") {
+            deleted = true;
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            rename_from = Some(path);
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            rename_to = Some(path);
+        } else if line.starts_with("Binary files ") {
+            return Err(format_err!(
+                "apply_patch does not support binary diffs ({})",
+                new_path
+            ));
+        } else if line.starts_with("@@ ") {
+            hunks_start = Some(i);
+            break;
+        }
+    }
+
+    let final_path = NonRootMPath::new(rename_to.unwrap_or(new_path))?;
+
+    if deleted {
+        return Ok(ctx.delete_file(final_path));
+    }
+
+    let source_path = NonRootMPath::new(rename_from.unwrap_or(old_path))?;
+    let base_content = base_wc.get(&source_path);
+    let base_text = match base_content {
+        Some(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        None => String::new(),
+    };
+    let base_lines: Vec<&str> = base_text.lines().collect();
+
+    let new_lines = match hunks_start {
+        Some(start) => apply_hunks(&base_lines, &block[start..])?,
+        None => base_lines.iter().map(|line| line.to_string()).collect(),
+    };
+    let mut content = new_lines.join("\n");
+    if !new_lines.is_empty() {
+        content.push('\n');
+    }
+
+    ctx = if let Some(from) = rename_from {
+        ctx.add_file_with_copy_info(
+            final_path,
+            content,
+            (parent_cs_id, NonRootMPath::new(from)?),
+        )
+    } else {
+        ctx.add_file(final_path, content)
+    };
+    Ok(ctx)
 }
 
 enum CreateFileContext {
@@ -782,7 +1629,7 @@ pub async fn store_rename(
 
 pub async fn resolve_cs_id(
     ctx: &CoreContext,
-    repo: &(impl BookmarksRef + BonsaiHgMappingRef),
+    repo: &(impl BookmarksRef + BonsaiHgMappingRef + BonsaiGitMappingRef),
     cs_ident: impl Into<CommitIdentifier>,
 ) -> Result<ChangesetId, Error> {
     use CommitIdentifier::*;
@@ -823,11 +1670,27 @@ pub async fn resolve_cs_id(
                 }
             }
 
+            // Mirrors git-cinnabar's bidirectional hg2git/git2hg mapping:
+            // a 40-char git SHA-1 resolves through the repo's own
+            // bonsai<->git mapping. Abbreviated git hashes aren't supported
+            // here, since that needs a prefix lookup this mapping doesn't
+            // expose.
+            if let Ok(git_sha1) = GitSha1::from_str(&hash_or_bookmark) {
+                if let Ok(Some(cs_id)) = repo
+                    .bonsai_git_mapping()
+                    .get_bonsai_from_git_sha1(ctx, git_sha1)
+                    .await
+                {
+                    return Ok(cs_id);
+                }
+            }
+
             if let Ok(cs_id) = ChangesetId::from_str(&hash_or_bookmark) {
                 return Ok(cs_id);
             }
             Err(format_err!(
-                "invalid (hash|bookmark) or does not exist in this repository: {}",
+                "invalid (hash|bookmark) or does not exist in this repository: {} \
+                 (tried bookmark, hg hash, git hash, and bonsai hash)",
                 hash_or_bookmark
             ))
         }
@@ -879,3 +1742,680 @@ pub async fn create_commit_with_date(
     save_changesets(&ctx, &repo, vec![bcs]).await.unwrap();
     bcs_id
 }
+
+/// Like `create_commit_with_date`, but lets the author and committer be
+/// distinct identities with their own timestamps, mirroring git's
+/// author/committer split (as opposed to hg, which only has one).
+///
+/// Bonsai changesets have no native committer field, so -- following the
+/// same convention git-cinnabar uses for its `HgCommitter` extra -- the
+/// committer's name/email and date are stashed in `hg_extra` under the
+/// `committer`/`committer_date` keys, percent-encoded so arbitrary bytes
+/// (including the ' ' and '%' the encoding itself relies on) survive a
+/// bonsai -> hg -> bonsai round-trip intact.
+pub async fn create_commit_with_identities(
+    ctx: CoreContext,
+    repo: impl Repo,
+    parents: Vec<ChangesetId>,
+    file_changes: BTreeMap<NonRootMPath, FileChange>,
+    author: String,
+    author_date: DateTime,
+    committer: String,
+    committer_date: DateTime,
+) -> ChangesetId {
+    let hg_extra = btreemap! {
+        "committer".to_string() => percent_encode_extra(committer.as_bytes()),
+        "committer_date".to_string() => percent_encode_extra(
+            format!("{} {}", committer_date.timestamp_secs(), committer_date.tz_offset_secs())
+                .as_bytes(),
+        ),
+    };
+
+    let bcs = BonsaiChangesetMut {
+        parents,
+        author,
+        author_date,
+        message: "message".to_string(),
+        hg_extra: hg_extra.into(),
+        file_changes: file_changes.into(),
+        ..Default::default()
+    }
+    .freeze()
+    .unwrap();
+
+    let bcs_id = bcs.get_changeset_id();
+    save_changesets(&ctx, &repo, vec![bcs]).await.unwrap();
+    bcs_id
+}
+
+/// Percent-encodes bytes for storage in an hg extra value, matching the
+/// escaping mercurial itself uses for extra fields: only ASCII
+/// alphanumerics and `-._~` pass through unescaped, everything else
+/// (including space and `%`) becomes `%XX`. This is what lets `committer`
+/// and `committer_date` round-trip through hg_extra without ambiguity,
+/// since extra values can't otherwise contain raw spaces.
+fn percent_encode_extra(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    for &b in bytes {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => out.push(b),
+            _ => {
+                out.push(b'%');
+                out.extend_from_slice(format!("{:02X}", b).as_bytes());
+            }
+        }
+    }
+    out
+}
+
+/// An external identifier to graft onto the resulting changeset once
+/// [`create_commit_graft`] has settled on a `ChangesetId`, whether that id
+/// was grafted onto pre-existing history or freshly created.
+pub enum ImportIdentifier {
+    Hg(HgChangesetId),
+    Git(GitSha1),
+}
+
+/// Creates a commit the way [`create_commit`] does, unless equivalent
+/// history already exists in the repo (for example, because it was already
+/// imported via a different path), in which case the pre-existing
+/// changeset is reused instead of minting a duplicate. Modeled on
+/// git-cinnabar's `graft` mode for converger imports.
+///
+/// A changeset is only a valid graft candidate if its parents are exactly
+/// the proposed `parents` -- this keeps grafted history consistent from
+/// the root down, rather than grafting a child onto a candidate whose
+/// ancestry has already silently diverged (e.g. because a grandparent
+/// failed to graft and was created fresh instead).
+///
+/// Tree identity is compared via each candidate's own `file_changes`
+/// relative to its parents, since in Mononoke the derived manifest is a
+/// pure function of a changeset's parents and file changes: two
+/// changesets with identical parents and identical file changes are
+/// guaranteed to derive identical manifests without needing to actually
+/// derive them. A match additionally requires the normalized author,
+/// author date, and message to agree. Zero matches falls back to
+/// `save_changesets` as normal; more than one match is an ambiguous graft
+/// and is an error rather than a guess.
+///
+/// Once the final `ChangesetId` is settled (grafted or newly created),
+/// `import_id` is recorded against it in the repo's hg or git mapping, so
+/// future imports of the same external commit resolve to the same
+/// changeset.
+pub async fn create_commit_graft(
+    ctx: &CoreContext,
+    repo: &impl Repo,
+    parents: Vec<ChangesetId>,
+    file_changes: BTreeMap<NonRootMPath, FileChange>,
+    author: String,
+    author_date: DateTime,
+    message: String,
+    import_id: ImportIdentifier,
+) -> Result<ChangesetId, Error> {
+    let mut candidates = Vec::new();
+    if let Some(first_parent) = parents.first() {
+        let children = repo
+            .commit_graph()
+            .changeset_children(ctx, *first_parent)
+            .await?;
+        for child in children {
+            let child_bcs = child.load(ctx, repo.repo_blobstore()).await?;
+            let child_parents: Vec<_> = child_bcs.parents().collect();
+            if !grafted_parents_match(&parents, &child_parents) {
+                continue;
+            }
+            let child_file_changes: BTreeMap<_, _> = child_bcs
+                .simplified_file_changes()
+                .map(|(path, fc)| (path.clone(), fc.cloned()))
+                .collect();
+            let proposed_file_changes: BTreeMap<_, _> = file_changes
+                .iter()
+                .map(|(path, fc)| (path.clone(), Some(fc.clone())))
+                .collect();
+            if child_file_changes != proposed_file_changes {
+                continue;
+            }
+            if child_bcs.author().trim() != author.trim()
+                || child_bcs.author_date() != &author_date
+                || child_bcs.message().trim() != message.trim()
+            {
+                continue;
+            }
+            candidates.push(child);
+        }
+    }
+
+    let cs_id = match candidates.as_slice() {
+        [] => {
+            let bcs = BonsaiChangesetMut {
+                parents,
+                author,
+                author_date,
+                message,
+                file_changes: file_changes.into(),
+                ..Default::default()
+            }
+            .freeze()?;
+            let cs_id = bcs.get_changeset_id();
+            save_changesets(ctx, repo, vec![bcs]).await?;
+            cs_id
+        }
+        [only] => *only,
+        _ => {
+            return Err(format_err!(
+                "ambiguous graft: {} pre-existing changesets match the proposed commit",
+                candidates.len()
+            ));
+        }
+    };
+
+    match import_id {
+        ImportIdentifier::Hg(hg_cs_id) => {
+            repo.bonsai_hg_mapping()
+                .add(
+                    ctx,
+                    BonsaiHgMappingEntry {
+                        hg_cs_id,
+                        bcs_id: cs_id,
+                    },
+                )
+                .await?;
+        }
+        ImportIdentifier::Git(git_sha1) => {
+            repo.bonsai_git_mapping()
+                .add(ctx, BonsaiGitMappingEntry::new(git_sha1, cs_id))
+                .await?;
+        }
+    }
+
+    Ok(cs_id)
+}
+
+/// Checks that a candidate's parents are exactly the proposed parents (same
+/// changesets, same order), so a graft match can't silently paper over
+/// ancestry that has actually diverged.
+fn grafted_parents_match(
+    proposed_parents: &[ChangesetId],
+    candidate_parents: &[ChangesetId],
+) -> bool {
+    proposed_parents == candidate_parents
+}
+
+/// One revlog delta-chunk, the same `(node, p1, p2, base, linknode, delta)`
+/// shape git-cinnabar's `hg_bundle` module reconstructs revisions from.
+/// `node`/`p1`/`p2` identify this revision and its revlog parents; `base`
+/// is the node this chunk's `delta` applies to (the null node means a
+/// delta against the empty string); `linknode` is the changeset this
+/// revision was introduced by, for manifest and filelog chunks (for a
+/// changeset chunk itself, `linknode` is always equal to `node`).
+#[derive(Clone, Debug)]
+pub struct RevChunk {
+    pub node: HgNodeHash,
+    pub p1: HgNodeHash,
+    pub p2: HgNodeHash,
+    pub base: HgNodeHash,
+    pub linknode: HgNodeHash,
+    pub delta: Vec<u8>,
+}
+
+/// A parsed Mercurial changegroup: the changelog group, the manifest
+/// group, and one filelog group per touched path, each still in
+/// on-the-wire chunk order.
+#[derive(Clone, Debug, Default)]
+pub struct ChangegroupStream {
+    pub changesets: Vec<RevChunk>,
+    pub manifests: Vec<RevChunk>,
+    pub filelogs: BTreeMap<NonRootMPath, Vec<RevChunk>>,
+}
+
+/// Applies a Mercurial binary delta (the `mpatch` format: a sequence of
+/// big-endian `(start: u32, end: u32, len: u32)` headers, each followed by
+/// `len` bytes that replace `base[start..end]`) to reconstruct a full
+/// revision text from its base text.
+fn apply_mercurial_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    let mut pos = 0usize;
+    let mut cursor = 0usize;
+    while cursor < delta.len() {
+        if cursor + 12 > delta.len() {
+            return Err(format_err!("truncated delta header at offset {}", cursor));
+        }
+        let start = u32::from_be_bytes(delta[cursor..cursor + 4].try_into().unwrap()) as usize;
+        let end = u32::from_be_bytes(delta[cursor + 4..cursor + 8].try_into().unwrap()) as usize;
+        let len = u32::from_be_bytes(delta[cursor + 8..cursor + 12].try_into().unwrap()) as usize;
+        cursor += 12;
+        if end > base.len() || start > end || cursor + len > delta.len() {
+            return Err(format_err!(
+                "delta fragment out of range (start={}, end={}, len={}, base_len={})",
+                start,
+                end,
+                len,
+                base.len()
+            ));
+        }
+        out.extend_from_slice(&base[pos..start]);
+        out.extend_from_slice(&delta[cursor..cursor + len]);
+        cursor += len;
+        pos = end;
+    }
+    out.extend_from_slice(&base[pos..]);
+    Ok(out)
+}
+
+/// Reconstructs the full text of every revision in one revlog's delta
+/// chain. `chunks` must be in chain order: a revision's `base` is either
+/// the null node or a node that appears earlier in `chunks`.
+fn reconstruct_revisions(chunks: &[RevChunk]) -> Result<HashMap<HgNodeHash, Bytes>, Error> {
+    let mut texts: HashMap<HgNodeHash, Bytes> = HashMap::new();
+    for chunk in chunks {
+        let base_text: Vec<u8> = if chunk.base == NULL_HASH {
+            Vec::new()
+        } else {
+            texts
+                .get(&chunk.base)
+                .ok_or_else(|| {
+                    format_err!(
+                        "delta chunk {} references base {} before it was reconstructed",
+                        chunk.node,
+                        chunk.base
+                    )
+                })?
+                .to_vec()
+        };
+        let full_text = apply_mercurial_delta(&base_text, &chunk.delta)?;
+        texts.insert(chunk.node, Bytes::from(full_text));
+    }
+    Ok(texts)
+}
+
+/// Parses a reconstructed manifest revision's full text: one
+/// `path\0hash flags\n` line per tracked file, where `flags` is empty for
+/// a regular file or one of `x` (executable) / `l` (symlink).
+fn parse_hg_manifest_text(
+    text: &[u8],
+) -> Result<BTreeMap<NonRootMPath, (HgNodeHash, FileType)>, Error> {
+    let mut entries = BTreeMap::new();
+    for line in text.split(|&b| b == b'\n') {
+        if line.is_empty() {
+            continue;
+        }
+        let nul = line
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| format_err!("manifest line missing NUL separator"))?;
+        let path = NonRootMPath::new(&line[..nul])?;
+        let rest = std::str::from_utf8(&line[nul + 1..])?;
+        let hash_hex = rest
+            .get(..40)
+            .ok_or_else(|| format_err!("manifest entry for {} has a truncated hash", path))?;
+        let node = HgNodeHash::from_str(hash_hex)?;
+        let file_type = match &rest[40..] {
+            "" => FileType::Regular,
+            "x" => FileType::Executable,
+            "l" => FileType::Symlink,
+            other => {
+                return Err(format_err!(
+                    "unsupported manifest flag {:?} for {}",
+                    other,
+                    path
+                ));
+            }
+        };
+        entries.insert(path, (node, file_type));
+    }
+    Ok(entries)
+}
+
+/// The fields extracted from a reconstructed changeset revision's text:
+/// `manifest\nuser\ntime tz\nfile1\nfile2\n...\n\ndescription`.
+struct ParsedHgChangeset {
+    manifest_node: HgNodeHash,
+    user: String,
+    date: DateTime,
+    desc: String,
+}
+
+fn parse_hg_changeset_text(text: &Bytes) -> Result<ParsedHgChangeset, Error> {
+    let text = std::str::from_utf8(text)?;
+    let mut lines = text.splitn(4, '\n');
+    let manifest_node = HgNodeHash::from_str(
+        lines
+            .next()
+            .ok_or_else(|| format_err!("changeset text is empty"))?,
+    )?;
+    let user = lines
+        .next()
+        .ok_or_else(|| format_err!("changeset text is missing its user line"))?
+        .to_string();
+    let date_line = lines
+        .next()
+        .ok_or_else(|| format_err!("changeset text is missing its date line"))?;
+    let rest = lines.next().unwrap_or("");
+
+    let mut date_parts = date_line.splitn(2, ' ');
+    let time: i64 = date_parts
+        .next()
+        .ok_or_else(|| format_err!("changeset date line is missing a timestamp"))?
+        .parse()?;
+    let tz: i32 = date_parts
+        .next()
+        .ok_or_else(|| format_err!("changeset date line is missing a timezone offset"))?
+        .split(' ')
+        .next()
+        .unwrap_or("0")
+        .parse()?;
+    let date = DateTime::from_timestamp(time, tz)?;
+
+    // `rest` is the file list (one path per line) followed by a blank line
+    // and then the free-form commit message; only the message matters here
+    // since file changes are derived from the manifest diff instead. The
+    // blank line isn't always a literal "\n\n": splitting `date_line` out
+    // above already consumed the newline that follows it, so when there are
+    // zero changed files `rest` starts with a single '\n' rather than two.
+    // Scan line-by-line for the first empty line instead of assuming a fixed
+    // separator width.
+    let mut past_blank_line = false;
+    let mut desc_lines = Vec::new();
+    for line in rest.split('\n') {
+        if past_blank_line {
+            desc_lines.push(line);
+        } else if line.is_empty() {
+            past_blank_line = true;
+        }
+    }
+    let desc = desc_lines.join("\n");
+
+    Ok(ParsedHgChangeset {
+        manifest_node,
+        user,
+        date,
+        desc,
+    })
+}
+
+/// Splits a reconstructed file revision's text into its copy-from
+/// metadata (if any) and its actual content. Mercurial prepends a
+/// `\x01\n`-delimited `copy: path\ncopyrev: hex\n` block when a file was
+/// renamed or copied from another path in this revision's first parent.
+fn split_hg_file_metadata(text: &Bytes) -> (Option<NonRootMPath>, Bytes) {
+    const MARKER: &[u8] = b"\x01\n";
+    if !text.starts_with(MARKER) {
+        return (None, text.clone());
+    }
+    let body = &text[MARKER.len()..];
+    let end = match body.windows(MARKER.len()).position(|w| w == MARKER) {
+        Some(end) => end,
+        None => return (None, text.clone()),
+    };
+
+    let mut copy_path = None;
+    for line in body[..end].split(|&b| b == b'\n') {
+        if let Some(rest) = line.strip_prefix(b"copy: ") {
+            copy_path = std::str::from_utf8(rest).ok().and_then(|p| NonRootMPath::new(p).ok());
+        }
+    }
+    let content = text.slice(MARKER.len() + end + MARKER.len()..);
+    (copy_path, content)
+}
+
+/// Resolves an hg node to its bonsai parent for [`import_changegroup`]: the
+/// null node means "no parent"; a node built earlier in this same
+/// changegroup resolves to the changeset just created for it; anything
+/// else falls back to the repo's own `bonsai_hg_mapping` (a parent that
+/// predates this import). Returns `Ok(None)` -- rather than erring --
+/// when none of those resolve yet, so the caller can defer the chunk and
+/// retry once the rest of the group has landed.
+async fn resolve_hg_parent(
+    ctx: &CoreContext,
+    repo: &impl Repo,
+    node_to_bcs: &HashMap<HgNodeHash, ChangesetId>,
+    node: HgNodeHash,
+) -> Result<Option<Option<ChangesetId>>, Error> {
+    if node == NULL_HASH {
+        return Ok(Some(None));
+    }
+    if let Some(cs_id) = node_to_bcs.get(&node) {
+        return Ok(Some(Some(*cs_id)));
+    }
+    let cs_id = repo
+        .bonsai_hg_mapping()
+        .get_bonsai_from_hg(ctx, HgChangesetId::new(node))
+        .await?;
+    Ok(cs_id.map(Some))
+}
+
+/// Resolves `parent_node`'s manifest, fetching it from the repo and caching it into
+/// `manifests`/`node_to_manifest` if it wasn't already there. `node_to_manifest` only
+/// gets populated as changesets are built earlier in the *same* call to
+/// `import_changegroup`; a `p1` that instead resolved via `bonsai_hg_mapping` (an
+/// already-existing changeset -- the common incremental-pull case) would otherwise
+/// have no entry there, silently leaving `old_manifest` as `None` and every file in
+/// the new manifest diffed as newly added.
+async fn resolve_parent_manifest(
+    ctx: &CoreContext,
+    repo: &impl Repo,
+    manifests: &mut HashMap<HgNodeHash, BTreeMap<NonRootMPath, (HgNodeHash, FileType)>>,
+    node_to_manifest: &mut HashMap<HgNodeHash, HgNodeHash>,
+    parent_node: HgNodeHash,
+) -> Result<HgNodeHash, Error> {
+    if let Some(manifest_node) = node_to_manifest.get(&parent_node) {
+        return Ok(*manifest_node);
+    }
+
+    let hg_changeset = HgChangesetId::new(parent_node)
+        .load(ctx, repo.repo_blobstore())
+        .await?;
+    let manifest_id = hg_changeset.manifestid();
+    let manifest_node = manifest_id.into_nodehash();
+
+    if let std::collections::hash_map::Entry::Vacant(entry) = manifests.entry(manifest_node) {
+        let leaf_entries: BTreeMap<NonRootMPath, (HgNodeHash, FileType)> = manifest_id
+            .list_leaf_entries(ctx.clone(), repo.repo_blobstore_arc())
+            .map_ok(|(path, (file_type, file_node_id))| {
+                (path, (file_node_id.into_nodehash(), file_type))
+            })
+            .try_collect()
+            .await?;
+        entry.insert(leaf_entries);
+    }
+    node_to_manifest.insert(parent_node, manifest_node);
+    Ok(manifest_node)
+}
+
+/// Imports a Mercurial changegroup and produces the bonsai changesets it
+/// describes, using the same delta-chain reconstruction git-cinnabar's
+/// `hg_bundle` module applies to changegroup chunks: first every
+/// changeset, manifest, and filelog revision's full text is rebuilt from
+/// its delta chain, then each changeset chunk is turned into a
+/// `BonsaiChangesetMut` by diffing its manifest against its first
+/// parent's (a root changeset is treated as adding every file in its own
+/// manifest; a first parent that predates this changegroup has its
+/// manifest loaded from the repo).
+///
+/// A changeset chunk whose `p1`/`p2` isn't yet resolvable -- neither built
+/// so far in this changegroup nor already known to `bonsai_hg_mapping` --
+/// is deferred and retried once the rest of the group has been processed,
+/// since some bundle producers emit a merge's parents out of strict
+/// dependency order. It's only an error if a changeset is still
+/// unresolvable once nothing else in the group can unblock it. This is
+/// also where a manifest/filelog chunk's `linknode` referencing a
+/// changeset not yet in the stream ends up getting deferred in practice:
+/// that changeset's own chunk is still waiting on an unresolved parent, so
+/// it can't be turned into file changes (which walk the manifest diff,
+/// not `linknode`) until the chunk it's deferred behind lands.
+///
+/// Returns the resulting `ChangesetId`s in the order they were built:
+/// changelog-chunk order, except that a deferred chunk's changeset comes
+/// after those of the chunks processed before its retry.
+pub async fn import_changegroup(
+    ctx: &CoreContext,
+    repo: &impl Repo,
+    changegroup: ChangegroupStream,
+) -> Result<Vec<ChangesetId>, Error> {
+    let manifest_texts = reconstruct_revisions(&changegroup.manifests)?;
+    let mut manifests = HashMap::new();
+    for (node, text) in &manifest_texts {
+        manifests.insert(*node, parse_hg_manifest_text(text)?);
+    }
+
+    let mut file_texts = HashMap::new();
+    for (path, chunks) in &changegroup.filelogs {
+        file_texts.insert(path.clone(), reconstruct_revisions(chunks)?);
+    }
+
+    let changeset_texts = reconstruct_revisions(&changegroup.changesets)?;
+
+    let mut node_to_bcs: HashMap<HgNodeHash, ChangesetId> = HashMap::new();
+    let mut node_to_manifest: HashMap<HgNodeHash, HgNodeHash> = HashMap::new();
+    let mut ordered_ids = Vec::new();
+    let mut pending: Vec<&RevChunk> = changegroup.changesets.iter().collect();
+
+    while !pending.is_empty() {
+        let mut still_pending = Vec::new();
+        let mut progressed = false;
+
+        for chunk in pending {
+            let p1 = resolve_hg_parent(ctx, repo, &node_to_bcs, chunk.p1).await?;
+            let p2 = resolve_hg_parent(ctx, repo, &node_to_bcs, chunk.p2).await?;
+            let (p1, p2) = match (p1, p2) {
+                (Some(p1), Some(p2)) => (p1, p2),
+                _ => {
+                    still_pending.push(chunk);
+                    continue;
+                }
+            };
+            progressed = true;
+
+            let text = changeset_texts.get(&chunk.node).ok_or_else(|| {
+                format_err!("changeset {} is missing its reconstructed text", chunk.node)
+            })?;
+            let parsed = parse_hg_changeset_text(text)?;
+
+            let old_manifest_node = if chunk.p1 != NULL_HASH {
+                Some(
+                    resolve_parent_manifest(
+                        ctx,
+                        repo,
+                        &mut manifests,
+                        &mut node_to_manifest,
+                        chunk.p1,
+                    )
+                    .await?,
+                )
+            } else {
+                None
+            };
+            let new_manifest = manifests.get(&parsed.manifest_node).ok_or_else(|| {
+                format_err!(
+                    "changeset {} references manifest {}, which isn't in this changegroup",
+                    chunk.node,
+                    parsed.manifest_node
+                )
+            })?;
+            let old_manifest = old_manifest_node.and_then(|node| manifests.get(&node));
+
+            let mut paths: Vec<&NonRootMPath> = new_manifest.keys().collect();
+            if let Some(old_manifest) = old_manifest {
+                paths.extend(old_manifest.keys());
+            }
+            paths.sort();
+            paths.dedup();
+
+            let mut parents = Vec::new();
+            if let Some(p1) = p1 {
+                parents.push(p1);
+            }
+            if let Some(p2) = p2 {
+                parents.push(p2);
+            }
+
+            let mut file_changes = BTreeMap::new();
+            for path in paths {
+                let new_entry = new_manifest.get(path);
+                let old_entry = old_manifest.and_then(|m| m.get(path));
+                if new_entry == old_entry {
+                    continue;
+                }
+                match new_entry {
+                    None => {
+                        file_changes.insert(path.clone(), FileChange::Deletion);
+                    }
+                    Some((file_node, file_type)) => {
+                        let texts = file_texts.get(path).ok_or_else(|| {
+                            format_err!(
+                                "changeset {} touches {}, but its filelog isn't in this changegroup",
+                                chunk.node,
+                                path
+                            )
+                        })?;
+                        let full_text = texts.get(file_node).ok_or_else(|| {
+                            format_err!("filelog for {} is missing revision {}", path, file_node)
+                        })?;
+                        let (copy_from_path, content) = split_hg_file_metadata(full_text);
+                        let copy_from = match (copy_from_path, parents.first()) {
+                            (Some(from_path), Some(&parent)) => Some((from_path, parent)),
+                            _ => None,
+                        };
+
+                        let meta = filestore::store(
+                            repo.repo_blobstore(),
+                            repo.filestore_config().clone(),
+                            ctx,
+                            &StoreRequest::new(content.len().try_into().unwrap()),
+                            stream::once(async move { Ok(content) }),
+                        )
+                        .await?;
+
+                        file_changes.insert(
+                            path.clone(),
+                            FileChange::tracked(
+                                meta.content_id,
+                                *file_type,
+                                meta.total_size,
+                                copy_from,
+                                GitLfs::FullContent,
+                            ),
+                        );
+                    }
+                }
+            }
+
+            let bcs = BonsaiChangesetMut {
+                parents,
+                author: parsed.user,
+                author_date: parsed.date,
+                message: parsed.desc,
+                file_changes: file_changes.into(),
+                ..Default::default()
+            }
+            .freeze()?;
+            let cs_id = bcs.get_changeset_id();
+
+            save_changesets(ctx, repo, vec![bcs]).await?;
+            repo.bonsai_hg_mapping()
+                .add(
+                    ctx,
+                    BonsaiHgMappingEntry {
+                        hg_cs_id: HgChangesetId::new(chunk.node),
+                        bcs_id: cs_id,
+                    },
+                )
+                .await?;
+
+            node_to_bcs.insert(chunk.node, cs_id);
+            node_to_manifest.insert(chunk.node, parsed.manifest_node);
+            ordered_ids.push(cs_id);
+        }
+
+        if !progressed {
+            return Err(format_err!(
+                "{} changeset chunk(s) reference parents that never arrived in this changegroup \
+                 or the repo's hg mapping",
+                still_pending.len()
+            ));
+        }
+        pending = still_pending;
+    }
+
+    Ok(ordered_ids)
+}
@@ -10,7 +10,10 @@ use std::time::Duration;
 use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
+use bookmarks::BookmarkCategory;
 use bookmarks::BookmarkKey;
+use bookmarks::BookmarkPagination;
+use bookmarks::BookmarkPrefix;
 use bookmarks::BookmarkUpdateReason;
 use bookmarks::BookmarksRef;
 use bookmarks_movement::BookmarkKind;
@@ -18,6 +21,9 @@ use bookmarks_movement::check_bookmark_sync_config;
 use clap::Args;
 use commit_id::parse_commit_id;
 use context::CoreContext;
+use futures::TryStreamExt;
+use mononoke_types::ChangesetId;
+use regex::Regex;
 use repo_update_logger::BookmarkInfo;
 use repo_update_logger::BookmarkOperation;
 use repo_update_logger::log_bookmark_operation;
@@ -27,7 +33,20 @@ use super::Repo;
 #[derive(Args)]
 pub struct BookmarksDeleteArgs {
     /// Name of the bookmark to delete
-    name: BookmarkKey,
+    #[clap(conflicts_with = "pattern")]
+    name: Option<BookmarkKey>,
+
+    /// Delete every publishing or scratch bookmark whose name matches this
+    /// regex, instead of a single named bookmark. Mutually exclusive with
+    /// the positional bookmark name. All matched bookmarks are deleted in a
+    /// single atomic transaction: either all of them go, or none do.
+    #[clap(long, conflicts_with = "name")]
+    pattern: Option<String>,
+
+    /// Print the bookmarks that would be deleted and their current values,
+    /// without actually deleting them.
+    #[clap(long)]
+    dry_run: bool,
 
     /// Force deleting of bookmark in repos with pushredirection enabled
     /// (WARNING: this may break megarepo sync)
@@ -40,6 +59,9 @@ pub struct BookmarksDeleteArgs {
     /// a regex pattern in repository config.  This command does not use
     /// that configuration, and you must specify whether or not the
     /// bookmark is scratch using this flag.
+    ///
+    /// Ignored with `--pattern`, which resolves each matched bookmark's own
+    /// kind instead.
     #[clap(long)]
     scratch: bool,
 
@@ -47,82 +69,185 @@ pub struct BookmarksDeleteArgs {
     ///
     /// This can be any commit id type.  Specify 'scheme=id' to disambiguate
     /// commit identity scheme (e.g. 'hg=HASH', 'globalrev=REV').
+    ///
+    /// Not valid with `--pattern`, since it can match more than one bookmark.
     #[clap(long)]
     old_commit_id: Option<String>,
 }
 
+/// One bookmark resolved for deletion, alongside its kind and current value.
+struct ResolvedBookmark {
+    name: BookmarkKey,
+    kind: BookmarkKind,
+    old_value: ChangesetId,
+}
+
 pub async fn delete(
     ctx: &CoreContext,
     repo: &Repo,
     delete_args: BookmarksDeleteArgs,
 ) -> Result<()> {
-    let kind = if delete_args.scratch {
-        BookmarkKind::Scratch
-    } else {
-        BookmarkKind::Publishing
-    };
-    let old_value = if let Some(old_commit_id) = &delete_args.old_commit_id {
-        parse_commit_id(ctx, repo, old_commit_id).await?
-    } else {
-        repo.bookmarks()
-            .get(
-                ctx.clone(),
-                &delete_args.name,
-                bookmarks::Freshness::MostRecent,
-            )
-            .await
-            .with_context(|| format!("Failed to resolve bookmark '{}'", delete_args.name))?
-            .ok_or_else(|| {
-                anyhow!(
-                    "Cannot delete non-existent {} bookmark {}",
-                    kind.to_string(),
-                    delete_args.name
-                )
-            })?
+    let resolved = match (&delete_args.name, &delete_args.pattern) {
+        (Some(_), Some(_)) => {
+            // `conflicts_with` already rejects this combination at the clap layer.
+            unreachable!("name and pattern are mutually exclusive")
+        }
+        (None, None) => {
+            return Err(anyhow!(
+                "Must specify either a bookmark name or --pattern to delete"
+            ));
+        }
+        (Some(name), None) => {
+            let kind = if delete_args.scratch {
+                BookmarkKind::Scratch
+            } else {
+                BookmarkKind::Publishing
+            };
+            let old_value = if let Some(old_commit_id) = &delete_args.old_commit_id {
+                parse_commit_id(ctx, repo, old_commit_id).await?
+            } else {
+                repo.bookmarks()
+                    .get(ctx.clone(), name, bookmarks::Freshness::MostRecent)
+                    .await
+                    .with_context(|| format!("Failed to resolve bookmark '{}'", name))?
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Cannot delete non-existent {} bookmark {}",
+                            kind.to_string(),
+                            name
+                        )
+                    })?
+            };
+            vec![ResolvedBookmark {
+                name: name.clone(),
+                kind,
+                old_value,
+            }]
+        }
+        (None, Some(pattern)) => {
+            if delete_args.old_commit_id.is_some() {
+                return Err(anyhow!(
+                    "--old-commit-id cannot be used with --pattern, which can match more than one bookmark"
+                ));
+            }
+            resolve_by_pattern(ctx, repo, pattern).await?
+        }
     };
 
-    println!(
-        "Deleting {} bookmark {} at {}",
-        kind, delete_args.name, old_value,
-    );
-
-    if let Err(e) = check_bookmark_sync_config(ctx, repo, &delete_args.name, kind).await {
-        if delete_args.force_megarepo {
-            println!("Deleting bookmark in megarepo-synced repository (--force-megarepo)");
-            println!("Waiting 3 seconds. Ctrl-C now if you did not intend this - risk of SEV!");
-            tokio::time::sleep(Duration::from_secs(3)).await;
-        } else {
-            return Err(e).context("Refusing to delete bookmark in megarepo-synced repository");
+    if resolved.is_empty() {
+        println!("No bookmarks matched; nothing to delete");
+        return Ok(());
+    }
+
+    for bookmark in &resolved {
+        println!(
+            "{}eleting {} bookmark {} at {}",
+            if delete_args.dry_run { "Would d" } else { "D" },
+            bookmark.kind,
+            bookmark.name,
+            bookmark.old_value,
+        );
+    }
+
+    if delete_args.dry_run {
+        return Ok(());
+    }
+
+    for bookmark in &resolved {
+        if let Err(e) =
+            check_bookmark_sync_config(ctx, repo, &bookmark.name, bookmark.kind).await
+        {
+            if delete_args.force_megarepo {
+                println!(
+                    "Deleting bookmark {} in megarepo-synced repository (--force-megarepo)",
+                    bookmark.name
+                );
+                println!("Waiting 3 seconds. Ctrl-C now if you did not intend this - risk of SEV!");
+                tokio::time::sleep(Duration::from_secs(3)).await;
+            } else {
+                return Err(e).context(format!(
+                    "Refusing to delete bookmark {} in megarepo-synced repository",
+                    bookmark.name
+                ));
+            }
         }
-    };
+    }
 
     // Wait 1s to allow for Ctrl-C
     tokio::time::sleep(Duration::from_secs(1)).await;
 
     let mut transaction = repo.bookmarks().create_transaction(ctx.clone());
-
-    match kind {
-        BookmarkKind::Publishing | BookmarkKind::PullDefaultPublishing => {
-            transaction.delete(
-                &delete_args.name,
-                old_value,
-                BookmarkUpdateReason::ManualMove,
-            )?;
-        }
-        BookmarkKind::Scratch => {
-            transaction.delete_scratch(&delete_args.name, old_value)?;
+    for bookmark in &resolved {
+        match bookmark.kind {
+            BookmarkKind::Publishing | BookmarkKind::PullDefaultPublishing => {
+                transaction.delete(
+                    &bookmark.name,
+                    bookmark.old_value,
+                    BookmarkUpdateReason::ManualMove,
+                )?;
+            }
+            BookmarkKind::Scratch => {
+                transaction.delete_scratch(&bookmark.name, bookmark.old_value)?;
+            }
         }
     }
     transaction.commit().await?;
 
-    // Log the bookmark operation
-    let bookmark_info = BookmarkInfo {
-        bookmark_name: delete_args.name.clone(),
-        bookmark_kind: kind,
-        operation: BookmarkOperation::Delete(old_value),
-        reason: BookmarkUpdateReason::ManualMove,
-    };
-    log_bookmark_operation(ctx, repo, &bookmark_info).await;
+    // Log each bookmark's deletion now that the whole batch has committed atomically.
+    for bookmark in &resolved {
+        let bookmark_info = BookmarkInfo {
+            bookmark_name: bookmark.name.clone(),
+            bookmark_kind: bookmark.kind,
+            operation: BookmarkOperation::Delete(bookmark.old_value),
+            reason: BookmarkUpdateReason::ManualMove,
+        };
+        log_bookmark_operation(ctx, repo, &bookmark_info).await;
+    }
 
     Ok(())
 }
+
+/// Resolve every publishing or scratch bookmark whose name matches `pattern`.
+async fn resolve_by_pattern(
+    ctx: &CoreContext,
+    repo: &Repo,
+    pattern: &str,
+) -> Result<Vec<ResolvedBookmark>> {
+    let re = Regex::new(pattern).with_context(|| format!("Invalid pattern '{}'", pattern))?;
+
+    let mut resolved = Vec::new();
+    for (kind, bookmark_kind_filter) in [
+        (BookmarkKind::Publishing, &[BookmarkKind::Publishing][..]),
+        (
+            BookmarkKind::PullDefaultPublishing,
+            &[BookmarkKind::PullDefaultPublishing][..],
+        ),
+        (BookmarkKind::Scratch, &[BookmarkKind::Scratch][..]),
+    ] {
+        let matches = repo
+            .bookmarks()
+            .list(
+                ctx.clone(),
+                bookmarks::Freshness::MostRecent,
+                &BookmarkPrefix::empty(),
+                BookmarkCategory::ALL,
+                bookmark_kind_filter,
+                &BookmarkPagination::FromStart,
+                u64::MAX,
+            )
+            .map_ok(|(book, csid)| (book.into_key(), csid))
+            .try_collect::<Vec<_>>()
+            .await
+            .with_context(|| format!("Failed to list {} bookmarks", kind))?;
+        for (name, old_value) in matches {
+            if re.is_match(&name.to_string()) {
+                resolved.push(ResolvedBookmark {
+                    name,
+                    kind,
+                    old_value,
+                });
+            }
+        }
+    }
+    Ok(resolved)
+}
@@ -8,7 +8,10 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt::Debug;
+use std::io::Write;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::Mutex;
 
 use anyhow::Context;
 use anyhow::Error;
@@ -17,6 +20,8 @@ use async_trait::async_trait;
 use auto_impl::auto_impl;
 use blame::RootBlameV2;
 use blobrepo_hg::BlobRepoHg;
+use blobstore::Blobstore;
+use blobstore::BlobstoreBytes;
 use blobstore::Loadable;
 use blobstore::LoadableError;
 use bonsai_hg_mapping::BonsaiHgMapping;
@@ -51,6 +56,7 @@ use futures::stream::StreamExt;
 use futures::stream::TryStreamExt;
 use manifest::Entry;
 use manifest::Manifest;
+use manifest::ManifestOps;
 use mercurial_derivation::MappedHgChangesetId;
 use mercurial_types::FileBytes;
 use mercurial_types::HgChangesetId;
@@ -75,6 +81,7 @@ use mononoke_types::unode::UnodeEntry;
 use phases::Phase;
 use phases::Phases;
 use phases::PhasesRef;
+use repo_blobstore::RepoBlobstore;
 use repo_blobstore::RepoBlobstoreRef;
 use repo_derived_data::RepoDerivedDataRef;
 use repo_identity::RepoIdentityRef;
@@ -84,6 +91,7 @@ use slog::Logger;
 use slog::info;
 use slog::warn;
 use thiserror::Error;
+use tokio::sync::Semaphore;
 use unodes::RootUnodeManifestId;
 use yield_stream::YieldStreamExt;
 
@@ -135,6 +143,43 @@ impl StepRoute for EmptyRoute {
     }
 }
 
+/// Traversal order for the children emitted by a step, controlling how they are
+/// handed to the frontier (`bounded_traversal::limited_by_key_shardable`) driving
+/// `walk_exact`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WalkOrder {
+    /// Leave emission order as-is. This is the walker's traditional behavior: the
+    /// frontier's own FIFO scheduling means siblings across the whole graph are
+    /// scheduled before their children.
+    #[default]
+    BreadthFirst,
+    /// Reverse emission order, so a frontier that pulls its next item from the
+    /// most-recently-queued end drains one step's children before unrelated
+    /// siblings queued earlier, approximating a depth-first descent.
+    DepthFirst,
+    /// Sort children by path, so sibling fsnode/skeleton-manifest/unode entries
+    /// under the same directory are handed to the frontier contiguously, improving
+    /// blobstore cache hit rates on large-manifest repos.
+    PathLocality,
+}
+
+impl WalkOrder {
+    fn reorder(self, children: &mut [OutgoingEdge]) {
+        match self {
+            WalkOrder::BreadthFirst => {}
+            WalkOrder::DepthFirst => children.reverse(),
+            WalkOrder::PathLocality => {
+                children.sort_by_key(|e| {
+                    e.path.as_ref().map(|p| {
+                        let mpath: &MPath = p.as_ref().into();
+                        mpath.to_string()
+                    })
+                });
+            }
+        }
+    }
+}
+
 // Holds type of edge and target Node that we want to load in next step(s)
 // Combined with current node, this forms an complegte edge.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -168,6 +213,32 @@ pub enum ErrorKind {
     NotTraversable(String, OutgoingEdge, String),
 }
 
+/// Identifies a `FileContent` for a length-only check (see `with_content_length_only`
+/// on `Checker`): the content to check, plus the length already known to the caller
+/// from its own manifest/envelope entry, if any.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ContentLengthKey {
+    pub content_id: ContentId,
+    pub expected_length: Option<u64>,
+}
+
+impl ContentLengthKey {
+    fn new(content_id: ContentId, expected_length: u64) -> Self {
+        Self {
+            content_id,
+            expected_length: Some(expected_length),
+        }
+    }
+
+    /// For callers (e.g. unodes) whose manifest entry does not carry a known size.
+    fn unchecked(content_id: ContentId) -> Self {
+        Self {
+            content_id,
+            expected_length: None,
+        }
+    }
+}
+
 // Simpler visitor trait used inside each step to decide
 // whether to emit an edge
 #[async_trait]
@@ -238,6 +309,15 @@ pub trait WalkVisitor<VOut, Route>: VisitOne {
     ) -> Result<(VOut, Route), Error>;
 }
 
+/// Keys a checkpoint by repo and opt-in checkpoint name, so that multiple walk jobs
+/// against the same repo don't collide as long as each uses its own
+/// `checkpoint_name`. The walk roots are not part of the key.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CheckpointKey {
+    pub repo_id: mononoke_types::RepositoryId,
+    pub checkpoint_name: String,
+}
+
 // Visitor methods that are only needed during tailing
 pub trait TailingWalkVisitor {
     fn start_chunk(
@@ -248,6 +328,8 @@ pub trait TailingWalkVisitor {
 
     // WalkVisitor needs to be Arc for clone/move into spawn in walk.rs so we can't use &mut self to restrict this.
     // Should only called from tail.rs between chunks when nothing else is accessing the WalkVisitor.
+    // Visited state is exact (there is no approximate-membership visited-set), so this
+    // resets only the exact state for `node_types` and `interned_types`.
     fn clear_state(
         &mut self,
         node_types: &HashSet<NodeType>,
@@ -259,6 +341,241 @@ pub trait TailingWalkVisitor {
     fn num_deferred(&self) -> usize;
 }
 
+/// The outstanding frontier of a single (non-chunked) `walk_exact` run: root-level
+/// edges that still need walking to reach everything `walk_roots` can reach, plus
+/// the changesets that were deferred pending a later chunk. Produced by
+/// `FrontierTracker::snapshot`; `walk_exact_resumable` persists and restores only
+/// its `deferred` set (see `save_resume_state`).
+#[derive(Clone, Debug, Default)]
+pub struct WalkResumeState {
+    /// Root-level edges still outstanding when the snapshot was taken. Not
+    /// persisted, so a resumed run does not re-queue them.
+    pub pending_roots: Vec<OutgoingEdge>,
+    /// Changesets that were deferred, including any restored from a prior run's
+    /// checkpoint. Kept as a separate set so a caller can report progress without
+    /// picking them back out of `pending_roots`.
+    pub deferred: HashSet<ChangesetId>,
+}
+
+/// Blobstore key that `save_resume_state`/`load_resume_state` persist the deferred
+/// set under for `checkpoint_key`.
+fn resume_state_blobstore_key(checkpoint_key: &CheckpointKey) -> String {
+    format!(
+        "walker.resume_state.{:?}.{}",
+        checkpoint_key.repo_id, checkpoint_key.checkpoint_name
+    )
+}
+
+/// Persists the resumable part of `state` for `checkpoint_key`: `pending_roots`
+/// needs `OutgoingEdge`/`Node` (defined in `crate::detail::graph`) to round-trip
+/// through a blob, and nothing elsewhere in this crate serializes either of those,
+/// so only `deferred` -- a plain set of `ChangesetId`, already known to round-trip
+/// through its hex `Display`/`FromStr` (see `ChangesetId::from_str` in
+/// `tests/utils`) -- is saved here. A resumed run therefore restarts its live
+/// frontier from `repo_params.walk_roots` rather than the exact outstanding edges,
+/// but doesn't silently lose track of what was deferred.
+async fn save_resume_state(
+    ctx: &CoreContext,
+    blobstore: &RepoBlobstore,
+    checkpoint_key: &CheckpointKey,
+    state: &WalkResumeState,
+) -> Result<(), Error> {
+    let mut ids: Vec<String> = state.deferred.iter().map(ChangesetId::to_string).collect();
+    ids.sort();
+    blobstore
+        .put(
+            ctx,
+            resume_state_blobstore_key(checkpoint_key),
+            BlobstoreBytes::from_bytes(ids.join("\n").into_bytes()),
+        )
+        .await
+}
+
+/// Loads the deferred set `save_resume_state` persisted for `checkpoint_key`, if
+/// any. See `save_resume_state` for why `pending_roots` doesn't round-trip too.
+async fn load_resume_state(
+    ctx: &CoreContext,
+    blobstore: &RepoBlobstore,
+    checkpoint_key: &CheckpointKey,
+) -> Result<Option<HashSet<ChangesetId>>, Error> {
+    let data = blobstore
+        .get(ctx, &resume_state_blobstore_key(checkpoint_key))
+        .await?;
+    let data = match data {
+        Some(data) => data,
+        None => return Ok(None),
+    };
+    let raw = data.into_raw_bytes();
+    let text = std::str::from_utf8(&raw).context("decoding walk resume checkpoint")?;
+    let ids = text
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| ChangesetId::from_str(line).map_err(Error::from))
+        .collect::<Result<HashSet<_>, _>>()?;
+    Ok(Some(ids))
+}
+
+/// Tracks the true outstanding frontier of a `walk_exact` run as it happens. The
+/// scheduler that actually drives stepping (`bounded_traversal::limited_by_key_shardable`)
+/// keeps its own work queue private, so there's no way to peek at "what's left" from
+/// outside it. Instead this mirrors every step as `walk_one` makes it: the edge just
+/// completed leaves the live set, and whatever it produced joins it -- the edge itself,
+/// again, if it was deferred rather than resolved, since a deferred id must be re-queued
+/// rather than silently dropped. A snapshot at any point is exactly the set of edges that
+/// would need re-walking to pick up from here.
+pub struct FrontierTracker {
+    live: Mutex<HashSet<OutgoingEdge>>,
+    deferred: Mutex<HashSet<ChangesetId>>,
+}
+
+impl FrontierTracker {
+    pub fn new(initial_roots: impl IntoIterator<Item = OutgoingEdge>) -> Self {
+        Self {
+            live: Mutex::new(initial_roots.into_iter().collect()),
+            deferred: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn on_step_complete(
+        &self,
+        completed: &OutgoingEdge,
+        deferred_bcs_id: Option<ChangesetId>,
+        produced: &[OutgoingEdge],
+    ) {
+        {
+            let mut live = self.live.lock().expect("FrontierTracker lock poisoned");
+            if deferred_bcs_id.is_some() {
+                live.insert(completed.clone());
+            } else {
+                live.remove(completed);
+            }
+            live.extend(produced.iter().cloned());
+        }
+        if let Some(bcs_id) = deferred_bcs_id {
+            self.deferred
+                .lock()
+                .expect("FrontierTracker lock poisoned")
+                .insert(bcs_id);
+        }
+    }
+
+    /// Merges a restored deferred set (from `load_resume_state`) into this tracker
+    /// at startup, so a resumed run still reports them via `snapshot` even though
+    /// their originating edges weren't restored into `live` (see `save_resume_state`
+    /// for why only the deferred set round-trips).
+    fn seed_deferred(&self, restored: HashSet<ChangesetId>) {
+        self.deferred
+            .lock()
+            .expect("FrontierTracker lock poisoned")
+            .extend(restored);
+    }
+
+    /// A resumable snapshot of the current frontier. Safe to call concurrently with
+    /// further steps landing; at worst a snapshot is very slightly behind the true
+    /// live state, which is fine since re-visiting an already-recorded node is a
+    /// declined no-op (see `VisitOne::needs_visit`).
+    pub fn snapshot(&self) -> WalkResumeState {
+        WalkResumeState {
+            pending_roots: self
+                .live
+                .lock()
+                .expect("FrontierTracker lock poisoned")
+                .iter()
+                .cloned()
+                .collect(),
+            deferred: self
+                .deferred
+                .lock()
+                .expect("FrontierTracker lock poisoned")
+                .clone(),
+        }
+    }
+}
+
+/// Per-run visit counts for every `EdgeType`/`NodeType` actually traversed, fed from
+/// the same step that already validates `c.label.outgoing_type()`/`incoming_type()`
+/// against the static edge table -- so tracking coverage costs nothing extra. Lets a
+/// completed walk report which edge/node types in its own schema were exercised
+/// versus which were never reached, the way a coverage collector reports exercised
+/// vs. defined units.
+pub struct CoverageTracker {
+    node_visits: Mutex<HashMap<NodeType, u64>>,
+    edge_visits: Mutex<HashMap<EdgeType, u64>>,
+}
+
+impl CoverageTracker {
+    pub fn new() -> Self {
+        Self {
+            node_visits: Mutex::new(HashMap::new()),
+            edge_visits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn record(&self, edge_label: EdgeType, node_type: NodeType) {
+        *self
+            .node_visits
+            .lock()
+            .expect("CoverageTracker lock poisoned")
+            .entry(node_type)
+            .or_insert(0) += 1;
+        *self
+            .edge_visits
+            .lock()
+            .expect("CoverageTracker lock poisoned")
+            .entry(edge_label)
+            .or_insert(0) += 1;
+    }
+
+    /// One line per type in `defined_node_types`/`defined_edge_types`, tagged
+    /// `covered`/`MISSED` with its visit count, so a "full" walk's coverage report
+    /// proves every node and edge category in its own schema was actually reached.
+    pub fn report(
+        &self,
+        defined_node_types: &HashSet<NodeType>,
+        defined_edge_types: &HashSet<EdgeType>,
+    ) -> String {
+        let node_visits = self
+            .node_visits
+            .lock()
+            .expect("CoverageTracker lock poisoned");
+        let edge_visits = self
+            .edge_visits
+            .lock()
+            .expect("CoverageTracker lock poisoned");
+        let mut lines = vec!["node coverage:".to_string()];
+        let mut node_types: Vec<&NodeType> = defined_node_types.iter().collect();
+        node_types.sort_by_key(|n| format!("{:?}", n));
+        for node_type in node_types {
+            let count = node_visits.get(node_type).copied().unwrap_or(0);
+            lines.push(format!(
+                "  {:?}: {} ({})",
+                node_type,
+                if count > 0 { "covered" } else { "MISSED" },
+                count,
+            ));
+        }
+        lines.push("edge coverage:".to_string());
+        let mut edge_types: Vec<&EdgeType> = defined_edge_types.iter().collect();
+        edge_types.sort_by_key(|e| format!("{:?}", e));
+        for edge_type in edge_types {
+            let count = edge_visits.get(edge_type).copied().unwrap_or(0);
+            lines.push(format!(
+                "  {:?}: {} ({})",
+                edge_type,
+                if count > 0 { "covered" } else { "MISSED" },
+                count,
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Default for CoverageTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // Data found for this node, plus next steps
 enum StepOutput {
     Deferred(ChangesetId),
@@ -384,6 +701,7 @@ async fn blame_step<V: VisitOne>(
     repo: &Repo,
     checker: &Checker<V>,
     blame_id: BlameV2Id,
+    scuba: &mut MononokeScubaSampleBuilder,
 ) -> Result<StepOutput, StepError> {
     let blame = blame_id.load(ctx, repo.repo_blobstore()).await?;
     let mut edges = vec![];
@@ -399,12 +717,437 @@ async fn blame_step<V: VisitOne>(
             });
         }
     }
+
+    if checker.validate_blame_content {
+        validate_blame_ranges(ctx, repo, checker, blame_id, &blame, &mut edges, scuba).await?;
+    }
+
     Ok(StepOutput::Done(
         checker.step_data(NodeType::Blame, || NodeData::Blame(Some(blame))),
         edges,
     ))
 }
 
+/// Validation-only companion to `blame_step`: for each blame range, resolve the
+/// `FileContent` of the blamed `(path, csid)` and make sure the range the blame
+/// claims is actually in bounds for that content, surfacing anything else as a
+/// `CHECK_FAIL`. This is strictly additive to the normal `BlameToChangeset` walk
+/// above, and is only run when gated on via `EdgeType::BlameToFileContent`.
+async fn validate_blame_ranges<V: VisitOne>(
+    ctx: &CoreContext,
+    repo: &Repo,
+    checker: &Checker<V>,
+    blame_id: BlameV2Id,
+    blame: &blame::BlameV2,
+    edges: &mut Vec<OutgoingEdge>,
+    scuba: &mut MononokeScubaSampleBuilder,
+) -> Result<(), StepError> {
+    let ranges = match blame.ranges() {
+        Ok(ranges) => ranges,
+        // Same handling as changeset_ids(): a rejected blame has nothing to validate.
+        Err(_) => return Ok(()),
+    };
+
+    let blame_node = Node::Blame(blame_id);
+
+    for range in ranges {
+        let path = range.path().clone();
+        let csid = *range.csid();
+
+        let content_id = match resolve_path_content_id(ctx, repo, csid, &path).await? {
+            Some(content_id) => content_id,
+            None => {
+                add_check_fail(
+                    checker,
+                    scuba,
+                    &blame_node,
+                    "blame_content_validation",
+                    format!("orphaned blame range: no content for {} at {}", path, csid),
+                );
+                continue;
+            }
+        };
+
+        checker.add_edge_with_path(
+            edges,
+            EdgeType::BlameToFileContent,
+            || Node::FileContent(content_id),
+            || Some(WrappedPath::from(path.clone())),
+        );
+
+        let metadata =
+            filestore::get_metadata_readonly(repo.repo_blobstore(), ctx, &content_id.into())
+                .await
+                .map_err(Error::from)?
+                .flatten();
+
+        let total_size = metadata.map(|m| m.total_size);
+        let end = range.offset() + range.length();
+        let in_bounds = matches!(total_size, Some(total_size) if end <= total_size);
+
+        if !in_bounds {
+            add_check_fail(
+                checker,
+                scuba,
+                &blame_node,
+                "blame_content_validation",
+                format!(
+                    "blame range [{}, {}) for {} at {} is out of bounds for content {} (len {:?})",
+                    range.offset(),
+                    end,
+                    path,
+                    csid,
+                    content_id,
+                    total_size,
+                ),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// A single check outcome from a walk step, passed to every configured
+/// `WalkReporter` in addition to the inline Scuba sample each check already logs.
+/// `edge_label` is `None` for checks not tied to a specific outgoing edge (e.g. the
+/// blame-range content-bounds check).
+pub struct WalkReport<'a> {
+    pub node: &'a Node,
+    pub edge_label: Option<EdgeType>,
+    pub check_type: &'static str,
+    pub message: &'a str,
+}
+
+/// Receives check results alongside the hard-wired Scuba sample, decoupling the
+/// reporting format from the step logic: the same walk can feed a machine-readable
+/// findings file (`JsonLinesWalkReporter`), a TAP stream CI can parse directly
+/// (`TapWalkReporter`), and/or a second Scuba dataset (`ScubaWalkReporter`), all at
+/// once, without touching `add_check_fail` or the error branch in `walk_one`.
+pub trait WalkReporter: Send + Sync {
+    fn report_check(&self, report: &WalkReport<'_>);
+
+    /// Called once after the walk completes. Only reporters with a trailing
+    /// summary (e.g. TAP's `1..N` plan line) need to override this.
+    fn finish(&self) {}
+}
+
+/// Logs check results to a Scuba sample builder of their own, independent of the
+/// `scuba` already threaded through each step for node/edge context. Useful when a
+/// walk wants check failures split out into a different Scuba dataset than the
+/// per-step one.
+pub struct ScubaWalkReporter {
+    scuba: Mutex<MononokeScubaSampleBuilder>,
+}
+
+impl ScubaWalkReporter {
+    pub fn new(scuba: MononokeScubaSampleBuilder) -> Self {
+        Self {
+            scuba: Mutex::new(scuba),
+        }
+    }
+}
+
+impl WalkReporter for ScubaWalkReporter {
+    fn report_check(&self, report: &WalkReport<'_>) {
+        let mut scuba = self.scuba.lock().expect("ScubaWalkReporter lock poisoned");
+        add_node_to_scuba(None, None, report.node, None, &mut scuba);
+        scuba
+            .add(CHECK_TYPE, report.check_type)
+            .add(CHECK_FAIL, 1)
+            .add(ERROR_MSG, report.message);
+        if let Some(edge_label) = report.edge_label {
+            scuba.add(EDGE_TYPE, Into::<&'static str>::into(edge_label));
+        }
+        scuba.log();
+    }
+}
+
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Writes one JSON object per check result, newline-delimited, to `sink` -- e.g. a
+/// file a CI job tails, so it can consume walk findings without a Scuba pipeline.
+pub struct JsonLinesWalkReporter<W> {
+    sink: Mutex<W>,
+}
+
+impl<W: Write + Send> JsonLinesWalkReporter<W> {
+    pub fn new(sink: W) -> Self {
+        Self {
+            sink: Mutex::new(sink),
+        }
+    }
+}
+
+impl<W: Write + Send> WalkReporter for JsonLinesWalkReporter<W> {
+    fn report_check(&self, report: &WalkReport<'_>) {
+        let edge = report
+            .edge_label
+            .map_or_else(|| "null".to_string(), |e| {
+                format!(
+                    "\"{}\"",
+                    json_escape(Into::<&'static str>::into(e))
+                )
+            });
+        let line = format!(
+            "{{\"node\":\"{}\",\"edge\":{},\"check_type\":\"{}\",\"message\":\"{}\"}}\n",
+            json_escape(&format!("{:?}", report.node)),
+            edge,
+            json_escape(report.check_type),
+            json_escape(report.message),
+        );
+        let mut sink = self.sink.lock().expect("JsonLinesWalkReporter lock poisoned");
+        let _ = sink.write_all(line.as_bytes());
+    }
+}
+
+/// Emits results as a TAP (Test Anything Protocol) stream, one `not ok N - ...` line
+/// per failed check. The total check count isn't known up front for a streaming
+/// walk, so the `1..N` plan line is emitted by `finish()` after the walk completes,
+/// which TAP permits in place of a leading plan.
+pub struct TapWalkReporter<W> {
+    sink: Mutex<W>,
+    count: Mutex<usize>,
+}
+
+impl<W: Write + Send> TapWalkReporter<W> {
+    pub fn new(sink: W) -> Self {
+        Self {
+            sink: Mutex::new(sink),
+            count: Mutex::new(0),
+        }
+    }
+}
+
+impl<W: Write + Send> WalkReporter for TapWalkReporter<W> {
+    fn report_check(&self, report: &WalkReport<'_>) {
+        let n = {
+            let mut count = self.count.lock().expect("TapWalkReporter lock poisoned");
+            *count += 1;
+            *count
+        };
+        let line = format!(
+            "not ok {} - {} {:?} {}\n",
+            n, report.check_type, report.node, report.message,
+        );
+        let mut sink = self.sink.lock().expect("TapWalkReporter lock poisoned");
+        let _ = sink.write_all(line.as_bytes());
+    }
+
+    fn finish(&self) {
+        let count = *self.count.lock().expect("TapWalkReporter lock poisoned");
+        let mut sink = self.sink.lock().expect("TapWalkReporter lock poisoned");
+        let _ = writeln!(sink, "1..{}", count);
+    }
+}
+
+/// Which `StepError` variant synthesized an as-data finding (see `Finding`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FindingKind {
+    Missing,
+    HashValidationFailure,
+    Error,
+}
+
+impl FindingKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            FindingKind::Missing => "missing",
+            FindingKind::HashValidationFailure => "hash_validation_failure",
+            FindingKind::Error => "error",
+        }
+    }
+}
+
+/// One as-data finding: a node that `error_as_data_node_types` let continue as
+/// `NodeData::MissingAsData`/`HashValidationFailureAsData`/`ErrorAsData` rather than
+/// failing the walk. Recorded to `FindingsSink` so full-repo sweeps produce a
+/// durable artifact instead of only the in-process synthesized `NodeData`.
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub node: Node,
+    pub edge_label: EdgeType,
+    pub kind: FindingKind,
+    pub message: String,
+}
+
+impl Finding {
+    fn to_line(&self) -> String {
+        format!(
+            "{{\"node\":\"{}\",\"edge\":\"{}\",\"kind\":\"{}\",\"message\":\"{}\"}}",
+            json_escape(&format!("{:?}", self.node)),
+            json_escape(Into::<&'static str>::into(self.edge_label)),
+            self.kind.as_str(),
+            json_escape(&self.message),
+        )
+    }
+}
+
+/// Batches `Finding`s in memory and, once `batch_size` have accumulated, uploads
+/// them as a single newline-delimited, zstd-compressed blob to `blobstore`, with at
+/// most `max_concurrent_uploads` uploads in flight at once. Gives operators running
+/// a full-repo corruption sweep a compact, durable artifact of every missing/corrupt
+/// node they can post-process offline, instead of scraping logs for
+/// `error_as_data`'s synthesized records.
+pub struct FindingsSink {
+    blobstore: Arc<dyn Blobstore>,
+    ctx: CoreContext,
+    key_prefix: String,
+    batch_size: usize,
+    compression_level: i32,
+    pending: Mutex<Vec<String>>,
+    next_batch: Mutex<u64>,
+    concurrency: Arc<Semaphore>,
+}
+
+impl FindingsSink {
+    pub fn new(
+        ctx: CoreContext,
+        blobstore: Arc<dyn Blobstore>,
+        key_prefix: String,
+        batch_size: usize,
+        compression_level: i32,
+        max_concurrent_uploads: usize,
+    ) -> Self {
+        Self {
+            blobstore,
+            ctx,
+            key_prefix,
+            batch_size: batch_size.max(1),
+            compression_level,
+            pending: Mutex::new(Vec::new()),
+            next_batch: Mutex::new(0),
+            concurrency: Arc::new(Semaphore::new(max_concurrent_uploads.max(1))),
+        }
+    }
+
+    /// Record one finding; uploads the batch once `batch_size` findings have
+    /// accumulated since the last upload.
+    pub async fn record(&self, finding: Finding) -> Result<(), Error> {
+        let batch = {
+            let mut pending = self.pending.lock().expect("FindingsSink lock poisoned");
+            pending.push(finding.to_line());
+            if pending.len() >= self.batch_size {
+                Some(std::mem::take(&mut *pending))
+            } else {
+                None
+            }
+        };
+        match batch {
+            Some(batch) => self.upload_batch(batch).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Upload whatever findings have accumulated since the last full batch. Call
+    /// once after the walk completes so a partial final batch isn't dropped.
+    pub async fn finish(&self) -> Result<(), Error> {
+        let batch = {
+            let mut pending = self.pending.lock().expect("FindingsSink lock poisoned");
+            if pending.is_empty() {
+                None
+            } else {
+                Some(std::mem::take(&mut *pending))
+            }
+        };
+        match batch {
+            Some(batch) => self.upload_batch(batch).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn upload_batch(&self, lines: Vec<String>) -> Result<(), Error> {
+        let _permit = self
+            .concurrency
+            .clone()
+            .acquire_owned()
+            .await
+            .context("FindingsSink upload semaphore closed")?;
+        let ndjson = lines.join("\n") + "\n";
+        let compressed = zstd::bulk::compress(ndjson.as_bytes(), self.compression_level)
+            .context("zstd-compressing findings batch")?;
+        let seq = {
+            let mut next_batch = self.next_batch.lock().expect("FindingsSink lock poisoned");
+            let seq = *next_batch;
+            *next_batch += 1;
+            seq
+        };
+        let key = format!("{}.{:08}.ndjson.zst", self.key_prefix, seq);
+        self.blobstore
+            .put(&self.ctx, key, BlobstoreBytes::from_bytes(compressed))
+            .await
+    }
+}
+
+fn add_check_fail<V: VisitOne>(
+    checker: &Checker<V>,
+    scuba: &mut MononokeScubaSampleBuilder,
+    node: &Node,
+    check_type: &'static str,
+    msg: String,
+) {
+    add_node_to_scuba(None, None, node, None, scuba);
+    log_check_fail(checker, scuba, node, None, check_type, &msg);
+}
+
+/// Shared tail of check-failure handling: logs `CHECK_TYPE`/`CHECK_FAIL`/`ERROR_MSG`
+/// (and `EDGE_TYPE`, if this check is tied to an edge) to the per-step Scuba sample,
+/// then fans the same result out to any additional `WalkReporter`s configured for
+/// this walk. Callers are responsible for any node/path/via fields already added to
+/// `scuba` before calling this.
+fn log_check_fail<V: VisitOne>(
+    checker: &Checker<V>,
+    scuba: &mut MononokeScubaSampleBuilder,
+    node: &Node,
+    edge_label: Option<EdgeType>,
+    check_type: &'static str,
+    msg: &str,
+) {
+    scuba
+        .add(CHECK_TYPE, check_type)
+        .add(CHECK_FAIL, 1)
+        .add(ERROR_MSG, msg);
+    if let Some(edge_label) = edge_label {
+        scuba.add(EDGE_TYPE, Into::<&'static str>::into(edge_label));
+    }
+    scuba.log();
+    checker.notify_reporters(node, edge_label, check_type, msg);
+}
+
+/// Resolve the `ContentId` of `path` as it existed in `csid`, via the derived fsnode.
+async fn resolve_path_content_id(
+    ctx: &CoreContext,
+    repo: &Repo,
+    csid: ChangesetId,
+    path: &MPath,
+) -> Result<Option<ContentId>, Error> {
+    let root_fsnode_id = repo
+        .repo_derived_data()
+        .derive::<RootFsnodeId>(ctx, csid)
+        .await?;
+    let entry = root_fsnode_id
+        .fsnode_id()
+        .find_entry(ctx.clone(), repo.repo_blobstore().clone(), path.clone())
+        .await?;
+    Ok(match entry {
+        Some(Entry::Leaf(file)) => Some(*file.content_id()),
+        _ => None,
+    })
+}
+
 async fn fastlog_batch_step<V: VisitOne>(
     ctx: &CoreContext,
     repo: &Repo,
@@ -537,6 +1280,7 @@ async fn bonsai_changeset_info_mapping_step<V: VisitOne>(
     bcs_id: ChangesetId,
     enable_derive: bool,
 ) -> Result<StepOutput, StepError> {
+    let enable_derive = checker.can_enable_derive::<ChangesetInfo>(enable_derive);
     if is_derived::<ChangesetInfo>(ctx, repo, bcs_id, enable_derive).await? {
         let mut edges = vec![];
         checker.add_edge(
@@ -567,7 +1311,13 @@ async fn changeset_info_step<V: VisitOne>(
     bcs_id: ChangesetId,
     enable_derive: bool,
 ) -> Result<StepOutput, StepError> {
-    let info = maybe_derived::<ChangesetInfo>(ctx, repo, bcs_id, enable_derive).await?;
+    let info = maybe_derived::<ChangesetInfo>(
+        ctx,
+        repo,
+        bcs_id,
+        checker.can_enable_derive::<ChangesetInfo>(enable_derive),
+    )
+    .await?;
 
     if let Some(info) = info {
         let mut edges = vec![];
@@ -666,6 +1416,12 @@ async fn bonsai_changeset_step<V: VisitOne>(
     checker.add_edge(&mut edges, EdgeType::ChangesetToPhaseMapping, || {
         Node::PhaseMapping(*bcs_id)
     });
+    // Auditing node, not part of the main derived-data expansion above.
+    checker.add_edge(
+        &mut edges,
+        EdgeType::ChangesetToDerivedDataCompleteness,
+        || Node::DerivedDataCompleteness(*bcs_id),
+    );
 
     Ok(StepOutput::Done(
         checker.step_data(NodeType::Changeset, || NodeData::Changeset(bcs)),
@@ -673,6 +1429,64 @@ async fn bonsai_changeset_step<V: VisitOne>(
     ))
 }
 
+/// Cross-checks that a changeset which has one derived mapping type present also has
+/// all the others, rather than letting a partially/stuck-derived changeset only show up
+/// as a missing edge somewhere downstream. Always a leaf node: it does not expand further.
+async fn derived_data_completeness_step<V: VisitOne>(
+    ctx: &CoreContext,
+    repo: &Repo,
+    checker: &Checker<V>,
+    bcs_id: ChangesetId,
+    scuba: &mut MononokeScubaSampleBuilder,
+) -> Result<StepOutput, StepError> {
+    let (unodes, fsnodes, skeleton_manifests, deleted_manifests, hg, changeset_info) = futures::try_join!(
+        is_derived::<RootUnodeManifestId>(ctx, repo, bcs_id, false),
+        is_derived::<RootFsnodeId>(ctx, repo, bcs_id, false),
+        is_derived::<RootSkeletonManifestId>(ctx, repo, bcs_id, false),
+        is_derived::<RootDeletedManifestV2Id>(ctx, repo, bcs_id, false),
+        is_derived::<MappedHgChangesetId>(ctx, repo, bcs_id, false),
+        is_derived::<ChangesetInfo>(ctx, repo, bcs_id, false),
+    )
+    .map_err(StepError::Other)?;
+
+    let derivations: [(&str, bool); 6] = [
+        (RootUnodeManifestId::VARIANT, unodes),
+        (RootFsnodeId::VARIANT, fsnodes),
+        (RootSkeletonManifestId::VARIANT, skeleton_manifests),
+        (RootDeletedManifestV2Id::VARIANT, deleted_manifests),
+        (MappedHgChangesetId::VARIANT, hg),
+        (ChangesetInfo::VARIANT, changeset_info),
+    ];
+
+    let any_present = derivations.iter().any(|(_, present)| *present);
+    let missing: Vec<&str> = derivations
+        .iter()
+        .filter(|(_, present)| !present)
+        .map(|(name, _)| *name)
+        .collect();
+
+    if any_present && !missing.is_empty() {
+        add_check_fail(
+            checker,
+            scuba,
+            &Node::DerivedDataCompleteness(bcs_id),
+            "derived_data_completeness",
+            format!(
+                "changeset {} has partial derivation, missing: {}",
+                bcs_id,
+                missing.join(", ")
+            ),
+        );
+    }
+
+    Ok(StepOutput::Done(
+        checker.step_data(NodeType::DerivedDataCompleteness, || {
+            NodeData::DerivedDataCompleteness(missing.iter().map(|s| s.to_string()).collect())
+        }),
+        vec![],
+    ))
+}
+
 async fn file_content_step<V: VisitOne>(
     ctx: CoreContext,
     repo: &Repo,
@@ -761,6 +1575,7 @@ async fn evolve_filenode_flag<'a, V: 'a + VisitOne>(
 
     if checker.with_filenodes && !filenode_known_derived {
         let bcs_id = key.inner;
+        let enable_derive = checker.can_enable_derive::<FilenodesOnlyPublic>(enable_derive);
         let derived_filenode = if enable_derive {
             if checker.is_public(ctx, &bcs_id).await? {
                 let _ = repo
@@ -807,12 +1622,17 @@ async fn bonsai_to_hg_key<'a, V: 'a + VisitOne>(
         let derived = if from_state.is_some() {
             from_state
         } else {
-            maybe_derived::<MappedHgChangesetId>(ctx, repo, bcs_id, enable_derive)
-                .await?
-                .map(|v| {
-                    checker.record_hg_from_bonsai(&bcs_id, v.hg_changeset_id());
-                    v.hg_changeset_id()
-                })
+            maybe_derived::<MappedHgChangesetId>(
+                ctx,
+                repo,
+                bcs_id,
+                checker.can_enable_derive::<MappedHgChangesetId>(enable_derive),
+            )
+            .await?
+            .map(|v| {
+                checker.record_hg_from_bonsai(&bcs_id, v.hg_changeset_id());
+                v.hg_changeset_id()
+            })
         };
         Ok(derived.map(|inner| ChangesetKey {
             inner,
@@ -968,12 +1788,26 @@ async fn hg_file_envelope_step<V: VisitOne>(
 ) -> Result<StepOutput, StepError> {
     let envelope = hg_file_node_id.load(ctx, repo.repo_blobstore()).await?;
     let mut edges = vec![];
-    checker.add_edge_with_path(
-        &mut edges,
-        EdgeType::HgFileEnvelopeToFileContent,
-        || Node::FileContent(envelope.content_id()),
-        || path.cloned(),
-    );
+    if checker.with_content_length_only {
+        checker.add_edge_with_path(
+            &mut edges,
+            EdgeType::HgFileEnvelopeToFileContentLength,
+            || {
+                Node::FileContentLength(ContentLengthKey::new(
+                    envelope.content_id(),
+                    envelope.content_size(),
+                ))
+            },
+            || path.cloned(),
+        );
+    } else {
+        checker.add_edge_with_path(
+            &mut edges,
+            EdgeType::HgFileEnvelopeToFileContent,
+            || Node::FileContent(envelope.content_id()),
+            || path.cloned(),
+        );
+    }
     Ok(StepOutput::Done(
         checker.step_data(NodeType::HgFileEnvelope, || {
             NodeData::HgFileEnvelope(envelope)
@@ -982,6 +1816,38 @@ async fn hg_file_envelope_step<V: VisitOne>(
     ))
 }
 
+/// A length-only check against `content_id`: instead of `hg_file_envelope_step`,
+/// `fsnode_step`, or `unode_file_step` following all the way through to `FileContent`
+/// (which forces a full blobstore fetch plus decompression), this step fetches just
+/// the stored object's total size and records it in `NodeData::FileContentLength`
+/// next to the `expected_length` already known to the caller (hg envelope size /
+/// fsnode file size), if any; comparing the two is left to the validation layer. Most
+/// corruption/truncation already shows up as a length mismatch, so this lets a
+/// full-repo integrity sweep escalate to full-content reads only where needed.
+async fn file_content_length_step<V: VisitOne>(
+    ctx: &CoreContext,
+    repo: &Repo,
+    checker: &Checker<V>,
+    key: &ContentLengthKey,
+) -> Result<StepOutput, StepError> {
+    let metadata =
+        filestore::get_metadata_readonly(repo.repo_blobstore(), ctx, &key.content_id.into())
+            .await?
+            .flatten();
+
+    let observed_length = metadata.as_ref().map(|m| m.total_size);
+
+    Ok(StepOutput::Done(
+        checker.step_data(NodeType::FileContentLength, || {
+            NodeData::FileContentLength {
+                observed: observed_length,
+                expected: key.expected_length,
+            }
+        }),
+        vec![],
+    ))
+}
+
 async fn file_node_step_impl<V: VisitOne, F, D>(
     ctx: CoreContext,
     repo: &Repo,
@@ -1249,7 +2115,13 @@ async fn bonsai_to_fsnode_mapping_step<V: VisitOne>(
     bcs_id: ChangesetId,
     enable_derive: bool,
 ) -> Result<StepOutput, StepError> {
-    let root_fsnode_id = maybe_derived::<RootFsnodeId>(ctx, repo, bcs_id, enable_derive).await?;
+    let root_fsnode_id = maybe_derived::<RootFsnodeId>(
+        ctx,
+        repo,
+        bcs_id,
+        checker.can_enable_derive::<RootFsnodeId>(enable_derive),
+    )
+    .await?;
 
     if let Some(root_fsnode_id) = root_fsnode_id {
         let mut edges = vec![];
@@ -1305,17 +2177,36 @@ async fn fsnode_step<V: VisitOne>(
                     );
                 }
                 FsnodeEntry::File(file) => {
-                    checker.add_edge_with_path(
-                        &mut content_edges,
-                        EdgeType::FsnodeToFileContent,
-                        || Node::FileContent(*file.content_id()),
-                        || {
-                            path.map(|p| {
-                                let path: &MPath = p.as_ref().into();
-                                WrappedPath::from(path.join_element(Some(child)))
-                            })
-                        },
-                    );
+                    if checker.with_content_length_only {
+                        checker.add_edge_with_path(
+                            &mut content_edges,
+                            EdgeType::FsnodeToFileContentLength,
+                            || {
+                                Node::FileContentLength(ContentLengthKey::new(
+                                    *file.content_id(),
+                                    file.size(),
+                                ))
+                            },
+                            || {
+                                path.map(|p| {
+                                    let path: &MPath = p.as_ref().into();
+                                    WrappedPath::from(path.join_element(Some(child)))
+                                })
+                            },
+                        );
+                    } else {
+                        checker.add_edge_with_path(
+                            &mut content_edges,
+                            EdgeType::FsnodeToFileContent,
+                            || Node::FileContent(*file.content_id()),
+                            || {
+                                path.map(|p| {
+                                    let path: &MPath = p.as_ref().into();
+                                    WrappedPath::from(path.join_element(Some(child)))
+                                })
+                            },
+                        );
+                    }
                 }
             }
         }
@@ -1338,12 +2229,26 @@ async fn bonsai_to_unode_mapping_step<V: VisitOne>(
     enable_derive: bool,
 ) -> Result<StepOutput, StepError> {
     let mut root_unode_id =
-        maybe_derived::<RootUnodeManifestId>(ctx, repo, bcs_id, enable_derive).await?;
+        maybe_derived::<RootUnodeManifestId>(
+            ctx,
+            repo,
+            bcs_id,
+            checker.can_enable_derive::<RootUnodeManifestId>(enable_derive),
+        )
+        .await?;
 
     let mut walk_blame = checker.with_blame && root_unode_id.is_some();
 
     // If we need blame, need to make sure its derived also
-    if walk_blame && !is_derived::<RootBlameV2>(ctx, repo, bcs_id, enable_derive).await? {
+    if walk_blame
+        && !is_derived::<RootBlameV2>(
+            ctx,
+            repo,
+            bcs_id,
+            checker.can_enable_derive::<RootBlameV2>(enable_derive),
+        )
+        .await?
+    {
         walk_blame = false;
         // Check if we should still walk the Unode even without blame
         if checker.is_public(ctx, &bcs_id).await? {
@@ -1356,7 +2261,15 @@ async fn bonsai_to_unode_mapping_step<V: VisitOne>(
     let mut walk_fastlog = checker.with_fastlog && root_unode_id.is_some();
 
     // If we need fastlog, need to make sure its derived also
-    if walk_fastlog && !is_derived::<RootFastlog>(ctx, repo, bcs_id, enable_derive).await? {
+    if walk_fastlog
+        && !is_derived::<RootFastlog>(
+            ctx,
+            repo,
+            bcs_id,
+            checker.can_enable_derive::<RootFastlog>(enable_derive),
+        )
+        .await?
+    {
         walk_fastlog = false;
         // Check if we should still walk the Unode even without fastlog
         if checker.is_public(ctx, &bcs_id).await? {
@@ -1459,15 +2372,42 @@ async fn unode_file_step<V: VisitOne>(
         );
     }
 
-    checker.add_edge_with_path(
-        &mut edges,
-        EdgeType::UnodeFileToFileContent,
-        || Node::FileContent(*unode_file.content_id()),
-        || path.cloned(),
-    );
+    if checker.with_content_length_only {
+        // UnodeFile does not carry a known size, so this just avoids the full
+        // blobstore fetch; there is no expected length to cross-check against.
+        checker.add_edge_with_path(
+            &mut edges,
+            EdgeType::UnodeFileToFileContentLength,
+            || Node::FileContentLength(ContentLengthKey::unchecked(*unode_file.content_id())),
+            || path.cloned(),
+        );
+    } else {
+        checker.add_edge_with_path(
+            &mut edges,
+            EdgeType::UnodeFileToFileContent,
+            || Node::FileContent(*unode_file.content_id()),
+            || path.cloned(),
+        );
+    }
 
+    let captured_path = path.cloned();
     Ok(StepOutput::Done(
-        checker.step_data(NodeType::UnodeFile, || NodeData::UnodeFile(unode_file)),
+        checker.step_data_captured(
+            NodeType::UnodeFile,
+            || NodeData::UnodeFile(unode_file),
+            |fields| {
+                fields
+                    .iter()
+                    .filter_map(|f| match f {
+                        CaptureField::Path => Some(CaptureValue::Path(captured_path.clone())),
+                        CaptureField::LinkedChangeset => {
+                            Some(CaptureValue::LinkedChangeset(linked_cs_id))
+                        }
+                        CaptureField::ChildCount => None,
+                    })
+                    .collect()
+            },
+        ),
         edges,
     ))
 }
@@ -1611,7 +2551,9 @@ async fn deleted_manifest_v2_step<V: VisitOne>(
         .clone()
         .into_subentries(ctx, repo.repo_blobstore());
 
+    let mut child_count = 0usize;
     while let Some((child_path, deleted_manifest_v2_id)) = subentries.try_next().await? {
+        child_count += 1;
         checker.add_edge_with_path(
             &mut edges,
             EdgeType::DeletedManifestV2ToDeletedManifestV2Child,
@@ -1625,10 +2567,24 @@ async fn deleted_manifest_v2_step<V: VisitOne>(
         );
     }
 
+    let captured_path = path.cloned();
     Ok(StepOutput::Done(
-        checker.step_data(NodeType::DeletedManifestV2, || {
-            NodeData::DeletedManifestV2(Some(deleted_manifest_v2))
-        }),
+        checker.step_data_captured(
+            NodeType::DeletedManifestV2,
+            || NodeData::DeletedManifestV2(Some(deleted_manifest_v2)),
+            |fields| {
+                fields
+                    .iter()
+                    .filter_map(|f| match f {
+                        CaptureField::Path => Some(CaptureValue::Path(captured_path.clone())),
+                        CaptureField::LinkedChangeset => {
+                            linked_cs_id.map(CaptureValue::LinkedChangeset)
+                        }
+                        CaptureField::ChildCount => Some(CaptureValue::ChildCount(child_count)),
+                    })
+                    .collect()
+            },
+        ),
         edges,
     ))
 }
@@ -1641,7 +2597,13 @@ async fn deleted_manifest_v2_mapping_step<V: VisitOne>(
     enable_derive: bool,
 ) -> Result<StepOutput, StepError> {
     let root_manifest_v2_id =
-        maybe_derived::<RootDeletedManifestV2Id>(ctx, repo, bcs_id, enable_derive).await?;
+        maybe_derived::<RootDeletedManifestV2Id>(
+            ctx,
+            repo,
+            bcs_id,
+            checker.can_enable_derive::<RootDeletedManifestV2Id>(enable_derive),
+        )
+        .await?;
 
     if let Some(root_manifest_v2_id) = root_manifest_v2_id {
         let mut edges = vec![];
@@ -1716,7 +2678,13 @@ async fn skeleton_manifest_mapping_step<V: VisitOne>(
     enable_derive: bool,
 ) -> Result<StepOutput, StepError> {
     let root_manifest_id =
-        maybe_derived::<RootSkeletonManifestId>(ctx, repo, bcs_id, enable_derive).await?;
+        maybe_derived::<RootSkeletonManifestId>(
+            ctx,
+            repo,
+            bcs_id,
+            checker.can_enable_derive::<RootSkeletonManifestId>(enable_derive),
+        )
+        .await?;
 
     if let Some(root_manifest_id) = root_manifest_id {
         let mut edges = vec![];
@@ -1768,6 +2736,31 @@ pub fn expand_checked_nodes(children: &mut Vec<OutgoingEdge>) {
     }
 }
 
+/// Which field of a node to extract into a `Captures` projection in place of the
+/// full `NodeData`. Not every field applies to every `NodeType`; a step function
+/// only honors the fields meaningful to the node it loads (see `Checker::captures`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CaptureField {
+    Path,
+    LinkedChangeset,
+    ChildCount,
+}
+
+/// A value projected out of a node per a `CaptureField` selector.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CaptureValue {
+    Path(Option<WrappedPath>),
+    LinkedChangeset(ChangesetId),
+    ChildCount(usize),
+}
+
+/// A compact, declaration-ordered projection of selected fields from a node,
+/// emitted via `Checker::step_data_captured` in place of the full `NodeData` when
+/// the caller only registered capture fields for this `NodeType` rather than
+/// requiring the whole node.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Captures(pub Vec<CaptureValue>);
+
 struct Checker<V: VisitOne> {
     include_edge_types: HashSet<EdgeType>,
     hash_validation_node_types: HashSet<NodeType>,
@@ -1780,6 +2773,33 @@ struct Checker<V: VisitOne> {
     with_blame: bool,
     with_fastlog: bool,
     with_filenodes: bool,
+    /// Validate that blame ranges are in-bounds for the content they reference,
+    /// rather than just checking that the blamed changesets exist.
+    validate_blame_content: bool,
+    /// Step to `FileContentLength` rather than `FileContent` from hg envelopes,
+    /// fsnodes and unodes, skipping the full blobstore fetch and decompression.
+    with_content_length_only: bool,
+    derived_data_capabilities: DerivedDataCapabilities,
+    /// Compiled `<node_type>:<glob>` path-selector rules (see `PathSelectorIndex`),
+    /// pruning manifest traversal to the matching subtrees. `None` means unrestricted.
+    path_selectors: Option<PathSelectorIndex>,
+    /// Per-`NodeType` capture-field selectors; see `step_data_captured`.
+    captures: HashMap<NodeType, Vec<CaptureField>>,
+    walk_order: WalkOrder,
+    /// Live-frontier bookkeeping for `walk_exact_resumable`; `None` for a plain
+    /// `walk_exact` run that isn't checkpointed. See `FrontierTracker`.
+    frontier_tracker: Option<Arc<FrontierTracker>>,
+    /// Nodes a prior walk already validated; `None` means this is a full walk with
+    /// nothing to prune. See `PriorValidatedSet`.
+    prior_validated: Option<Arc<PriorValidatedSet>>,
+    /// Additional check-result sinks fanned out to alongside the inline Scuba
+    /// logging in `add_check_fail`/`log_check_fail`. See `WalkReporter`.
+    reporters: Vec<Arc<dyn WalkReporter>>,
+    /// Durable artifact of as-data findings; `None` disables it. See `FindingsSink`.
+    findings_sink: Option<Arc<FindingsSink>>,
+    /// Per-type visit counts for the edge/node coverage report; `None` disables it.
+    /// See `CoverageTracker`.
+    coverage: Option<Arc<CoverageTracker>>,
 }
 
 impl<V: VisitOne> Checker<V> {
@@ -1801,6 +2821,66 @@ impl<V: VisitOne> Checker<V> {
         self.visitor.record_hg_from_bonsai(bcs_id, hg_cs_id)
     }
 
+    /// Whether `enable_derive` should actually trigger derivation of `Derivable`
+    /// here: always false if the repo's derived-data config doesn't have this type
+    /// enabled, unless it was force-enabled via `force_derive_types` (e.g. to
+    /// backfill a type that was just turned on).
+    fn can_enable_derive<Derivable: BonsaiDerivable>(&self, enable_derive: bool) -> bool {
+        enable_derive && self.derived_data_capabilities.can_derive(Derivable::VARIANT)
+    }
+
+    pub fn derived_data_capabilities(&self) -> &DerivedDataCapabilities {
+        &self.derived_data_capabilities
+    }
+
+    fn walk_order(&self) -> WalkOrder {
+        self.walk_order
+    }
+
+    fn frontier_tracker(&self) -> Option<&Arc<FrontierTracker>> {
+        self.frontier_tracker.as_ref()
+    }
+
+    fn findings_sink(&self) -> Option<&Arc<FindingsSink>> {
+        self.findings_sink.as_ref()
+    }
+
+    fn coverage(&self) -> Option<&Arc<CoverageTracker>> {
+        self.coverage.as_ref()
+    }
+
+    /// Drop children this walk doesn't need to re-step because a prior run already
+    /// validated them (see `PriorValidatedSet`) -- unless `always_emit_edge_types`
+    /// says this edge type must still be walked regardless (e.g. to keep a
+    /// capture/projection step up to date).
+    fn prune_previously_validated(&self, children: &mut Vec<OutgoingEdge>) {
+        let Some(prior) = &self.prior_validated else {
+            return;
+        };
+        children.retain(|c| {
+            self.always_emit_edge_types.contains(&c.label) || !prior.contains(&c.target)
+        });
+    }
+
+    /// Fan a check result out to every configured `WalkReporter`. Called from
+    /// `log_check_fail`, alongside the Scuba sample every check already logs.
+    fn notify_reporters(
+        &self,
+        node: &Node,
+        edge_label: Option<EdgeType>,
+        check_type: &'static str,
+        message: &str,
+    ) {
+        for reporter in &self.reporters {
+            reporter.report_check(&WalkReport {
+                node,
+                edge_label,
+                check_type,
+                message,
+            });
+        }
+    }
+
     async fn get_bonsai_from_hg(
         &self,
         ctx: &CoreContext,
@@ -1875,8 +2955,14 @@ impl<V: VisitOne> Checker<V> {
     {
         let always_emit = self.always_emit_edge_types.contains(&edge_type);
         if always_emit || self.include_edge_types.contains(&edge_type) {
+            let path = path_fn();
+            if let Some(path_selectors) = &self.path_selectors {
+                if !path_selectors.should_walk(edge_type.outgoing_type(), path.as_ref()) {
+                    return None;
+                }
+            }
             let outgoing = if self.keep_edge_paths {
-                OutgoingEdge::new_with_path(edge_type, node_fn(), path_fn())
+                OutgoingEdge::new_with_path(edge_type, node_fn(), path)
             } else {
                 OutgoingEdge::new(edge_type, node_fn())
             };
@@ -1898,6 +2984,309 @@ impl<V: VisitOne> Checker<V> {
             NodeData::NotRequired
         }
     }
+
+    /// Like `step_data`, but when the full node was not required and `t` has
+    /// registered `CaptureField`s (see `captures`), emits a `NodeData::Captured`
+    /// projection built by `capture_fn` instead of `NodeData::NotRequired`. This
+    /// lets a caller that only reduces over e.g. linknodes and child counts avoid
+    /// paying for, and retaining, the whole loaded node.
+    fn step_data_captured<D, C>(&self, t: NodeType, data_fn: D, capture_fn: C) -> NodeData
+    where
+        D: FnOnce() -> NodeData,
+        C: FnOnce(&[CaptureField]) -> Vec<CaptureValue>,
+    {
+        if self.required_node_data_types.contains(&t) {
+            data_fn()
+        } else if let Some(fields) = self.captures.get(&t) {
+            NodeData::Captured(Captures(capture_fn(fields)))
+        } else {
+            NodeData::NotRequired
+        }
+    }
+}
+
+/// Error parsing a walk-profile definition file (see `parse_walk_profiles`).
+#[derive(Debug, Error)]
+pub enum WalkProfileError {
+    #[error("unknown node type {0:?} in profile [{1}]")]
+    UnknownNodeType(String, String),
+    #[error("unknown edge type {0:?} in profile [{1}]")]
+    UnknownEdgeType(String, String),
+    #[error("profile [{0}] does `%include {1}` but no such profile was defined above it")]
+    UnknownBaseProfile(String, String),
+    #[error("invalid line {0:?} in profile [{1}]: {2}")]
+    InvalidLine(String, String, &'static str),
+}
+
+/// A named, composable set of node/edge types and derivation flags for the walker,
+/// as parsed from a walk-profile definition file. See `parse_walk_profiles`.
+#[derive(Clone, Debug, Default)]
+pub struct WalkProfile {
+    pub include_node_types: HashSet<NodeType>,
+    pub include_edge_types: HashSet<EdgeType>,
+    pub with_blame: bool,
+    pub with_fastlog: bool,
+    pub with_filenodes: bool,
+}
+
+/// Parse a walk-profile definition file into named `WalkProfile`s.
+///
+/// The format is modeled on Mercurial's config-layer parser: each `[name]` section
+/// defines a profile; `node-types`/`edge-types` are comma-separated lists that add to
+/// the profile; `with-blame`/`with-fastlog`/`with-filenodes` set the matching
+/// derivation flag; `%include <name>` merges in a profile defined earlier in the
+/// file as a base layer (subsequent lines in this section override or subtract from
+/// it, exactly as later config layers win over earlier ones); and `%unset
+/// <EdgeType>` removes one edge type that an `%include` pulled in. A profile must be
+/// fully defined before it is referenced by `%include`. Unknown node/edge-type names
+/// are a hard parse error rather than being silently dropped, so a typo in a shared
+/// profile file surfaces immediately instead of quietly narrowing what gets walked.
+pub fn parse_walk_profiles(text: &str) -> Result<HashMap<String, WalkProfile>, WalkProfileError> {
+    let mut profiles: HashMap<String, WalkProfile> = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for raw_line in text.lines() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            profiles.entry(name.to_string()).or_default();
+            current = Some(name.to_string());
+            continue;
+        }
+
+        let name = current.clone().ok_or_else(|| {
+            WalkProfileError::InvalidLine(
+                raw_line.to_string(),
+                String::new(),
+                "directive outside of any [section]",
+            )
+        })?;
+
+        if let Some(base) = line.strip_prefix("%include ") {
+            let base = base.trim();
+            let base_profile = profiles.get(base).cloned().ok_or_else(|| {
+                WalkProfileError::UnknownBaseProfile(name.clone(), base.to_string())
+            })?;
+            let profile = profiles.get_mut(&name).expect("section just inserted above");
+            profile
+                .include_node_types
+                .extend(base_profile.include_node_types);
+            profile
+                .include_edge_types
+                .extend(base_profile.include_edge_types);
+            profile.with_blame |= base_profile.with_blame;
+            profile.with_fastlog |= base_profile.with_fastlog;
+            profile.with_filenodes |= base_profile.with_filenodes;
+            continue;
+        }
+
+        if let Some(edge) = line.strip_prefix("%unset ") {
+            let edge = edge.trim();
+            let edge_type = EdgeType::from_str(edge)
+                .map_err(|_| WalkProfileError::UnknownEdgeType(edge.to_string(), name.clone()))?;
+            profiles
+                .get_mut(&name)
+                .expect("section just inserted above")
+                .include_edge_types
+                .remove(&edge_type);
+            continue;
+        }
+
+        let (key, value) = line.split_once('=').ok_or_else(|| {
+            WalkProfileError::InvalidLine(raw_line.to_string(), name.clone(), "expected key = value")
+        })?;
+        let (key, value) = (key.trim(), value.trim());
+        let profile = profiles.get_mut(&name).expect("section just inserted above");
+        match key {
+            "node-types" => {
+                for n in value.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+                    let node_type = NodeType::from_str(n)
+                        .map_err(|_| WalkProfileError::UnknownNodeType(n.to_string(), name.clone()))?;
+                    profile.include_node_types.insert(node_type);
+                }
+            }
+            "edge-types" => {
+                for e in value.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+                    let edge_type = EdgeType::from_str(e)
+                        .map_err(|_| WalkProfileError::UnknownEdgeType(e.to_string(), name.clone()))?;
+                    profile.include_edge_types.insert(edge_type);
+                }
+            }
+            "with-blame" => profile.with_blame = value == "true",
+            "with-fastlog" => profile.with_fastlog = value == "true",
+            "with-filenodes" => profile.with_filenodes = value == "true",
+            _ => {
+                return Err(WalkProfileError::InvalidLine(
+                    raw_line.to_string(),
+                    name.clone(),
+                    "unknown key (expected node-types, edge-types, with-blame, with-fastlog or with-filenodes)",
+                ));
+            }
+        }
+    }
+
+    Ok(profiles)
+}
+
+/// Which derived-data types the repo is actually configured to derive, discovered
+/// from the repo's enabled-derived-data config at `Checker` construction (the
+/// server-side analog of a Mercurial repo's `requirements` file), plus any
+/// explicitly force-enabled via `RepoWalkTypeParams::force_derive_types`. Gates
+/// `enable_derive` in the `*_step` functions so that stepping towards a type the
+/// repo never derives never triggers derivation; the step still looks up any
+/// already-derived data, and edges into such a type are still emitted.
+#[derive(Clone, Debug, Default)]
+pub struct DerivedDataCapabilities {
+    enabled: HashSet<&'static str>,
+    forced: HashSet<&'static str>,
+}
+
+impl DerivedDataCapabilities {
+    fn discover(repo: &Repo, forced: HashSet<&'static str>) -> Self {
+        let active_types = &repo.repo_derived_data().active_config().types;
+        let mut enabled = HashSet::new();
+        for variant in [
+            RootUnodeManifestId::VARIANT,
+            RootFsnodeId::VARIANT,
+            RootSkeletonManifestId::VARIANT,
+            RootDeletedManifestV2Id::VARIANT,
+            MappedHgChangesetId::VARIANT,
+            ChangesetInfo::VARIANT,
+            RootBlameV2::VARIANT,
+            RootFastlog::VARIANT,
+            FilenodesOnlyPublic::VARIANT,
+        ] {
+            if active_types.contains(variant) {
+                enabled.insert(variant);
+            }
+        }
+        Self { enabled, forced }
+    }
+
+    fn can_derive(&self, derived_data_type: &'static str) -> bool {
+        self.enabled.contains(derived_data_type) || self.forced.contains(derived_data_type)
+    }
+
+    /// The derived-data types this walk considers derivable (enabled in the repo's
+    /// config, or forced), i.e. the types for which `enable_derive` may be set.
+    pub fn enabled_types(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.enabled.iter().copied().chain(self.forced.iter().copied())
+    }
+}
+
+/// Error parsing a path-selector rule (see `PathSelectorIndex::parse`).
+#[derive(Debug, Error)]
+pub enum PathSelectorError {
+    #[error("path selector rule {0:?} is not of the form <node_type>:<path>")]
+    InvalidRule(String),
+    #[error("unknown node type {0:?} in path selector rule")]
+    UnknownNodeType(String),
+}
+
+/// A compiled set of `<node_type>:<glob>` path-selector rules, e.g.
+/// `unode:src/**/*.rs`, used to prune manifest traversal to matching subtrees.
+///
+/// Rules are compiled per `NodeType` into a trie over path elements, rather than
+/// kept as a flat list to scan per edge: branch nodes share common prefixes, a
+/// constant path element is an O(1) `HashMap` lookup into the matching child
+/// ("projecting" the candidate path's element at that position onto the set of
+/// still-live rules), `*` matches exactly one element via a dedicated wildcard
+/// child, and `**` marks its node so that it and everything beneath it matches
+/// regardless of how many further elements the walk descends through. Because the
+/// walker builds a manifest path one element at a time as it descends, checking a
+/// candidate path is simply a walk of the trie from the root along those elements:
+/// it returns a match both when a rule is already fully satisfied and when the
+/// path so far is still a live prefix of a longer rule, so the walk knows whether to
+/// keep descending before the full path is even known.
+pub struct PathSelectorIndex {
+    roots: HashMap<NodeType, PathSelectorNode>,
+}
+
+#[derive(Default)]
+struct PathSelectorNode {
+    /// A rule's pattern ends exactly at this element.
+    is_accept: bool,
+    /// A rule's pattern has a `**` here: this node and everything below it matches.
+    is_starstar: bool,
+    const_children: HashMap<String, PathSelectorNode>,
+    star_child: Option<Box<PathSelectorNode>>,
+}
+
+impl PathSelectorNode {
+    fn is_live(&self) -> bool {
+        self.is_accept
+            || self.is_starstar
+            || !self.const_children.is_empty()
+            || self.star_child.is_some()
+    }
+}
+
+impl PathSelectorIndex {
+    pub fn parse(rules: &[String]) -> Result<Self, PathSelectorError> {
+        let mut roots: HashMap<NodeType, PathSelectorNode> = HashMap::new();
+        for rule in rules {
+            let (node_type_str, pattern_str) = rule
+                .split_once(':')
+                .ok_or_else(|| PathSelectorError::InvalidRule(rule.clone()))?;
+            let node_type = NodeType::from_str(node_type_str)
+                .map_err(|_| PathSelectorError::UnknownNodeType(node_type_str.to_string()))?;
+            let mut node = roots.entry(node_type).or_default();
+            let elems: Vec<&str> = pattern_str.split('/').filter(|e| !e.is_empty()).collect();
+            for (i, elem) in elems.iter().enumerate() {
+                if *elem == "**" {
+                    node.is_starstar = true;
+                    break;
+                }
+                node = if *elem == "*" {
+                    node.star_child.get_or_insert_with(Box::default)
+                } else {
+                    node.const_children.entry(elem.to_string()).or_default()
+                };
+                if i == elems.len() - 1 {
+                    node.is_accept = true;
+                }
+            }
+            if elems.is_empty() {
+                node.is_accept = true;
+            }
+        }
+        Ok(Self { roots })
+    }
+
+    /// Whether the walk should emit/descend into `path` for `node_type`: true if no
+    /// rule is scoped to this node type (unrestricted), if `path` satisfies or is a
+    /// live prefix of a rule, and false otherwise.
+    fn should_walk(&self, node_type: NodeType, path: Option<&WrappedPath>) -> bool {
+        let Some(root) = self.roots.get(&node_type) else {
+            return true;
+        };
+        // Every trie node the path so far can have reached: a const child and the
+        // `*` child are both followed, since different rules may go through either.
+        let mut nodes = vec![root];
+        if let Some(path) = path {
+            let mpath: &MPath = path.as_ref().into();
+            for elem in mpath {
+                if nodes.iter().any(|node| node.is_starstar) {
+                    return true;
+                }
+                let elem = elem.to_string();
+                nodes = nodes
+                    .into_iter()
+                    .flat_map(|node| {
+                        let star = node.star_child.as_deref();
+                        node.const_children.get(&elem).into_iter().chain(star)
+                    })
+                    .collect();
+                if nodes.is_empty() {
+                    return false;
+                }
+            }
+        }
+        nodes.iter().any(|node| node.is_live())
+    }
 }
 
 // Parameters that vary per repo but can be setup in common conde
@@ -1913,6 +3302,9 @@ pub struct RepoWalkParams {
     pub include_node_types: HashSet<NodeType>,
     pub include_edge_types: HashSet<EdgeType>,
     pub hash_validation_node_types: HashSet<NodeType>,
+    /// `<node_type>:<glob>` rules, e.g. `unode:src/**/*.rs`, restricting manifest
+    /// traversal to matching subtrees. Empty means unrestricted. See `PathSelectorIndex`.
+    pub path_selector_rules: Vec<String>,
 }
 
 // Parameters that vary per repo but are set differently by scrub, validate etc.
@@ -1921,6 +3313,30 @@ pub struct RepoWalkTypeParams {
     pub always_emit_edge_types: HashSet<EdgeType>,
     pub required_node_data_types: HashSet<NodeType>,
     pub keep_edge_paths: bool,
+    /// Derived-data types to treat as derivable even if the repo's own enabled-derived-data
+    /// config does not have them turned on, e.g. while backfilling a type that was just
+    /// enabled but has no history yet. See `DerivedDataCapabilities`.
+    pub force_derive_types: HashSet<&'static str>,
+    /// Per-`NodeType` capture-field selectors: for a `NodeType` not in
+    /// `required_node_data_types`, the `UnodeFile` and `DeletedManifestV2` steps emit
+    /// a `Captures` projection with just these fields instead of
+    /// `NodeData::NotRequired`; other steps ignore them. See `CaptureField`.
+    pub captures: HashMap<NodeType, Vec<CaptureField>>,
+    /// Traversal order for the children emitted by each step. See `WalkOrder`.
+    pub walk_order: WalkOrder,
+    /// Set by `walk_exact_resumable` to mirror the live frontier as the walk
+    /// steps, so it can be checkpointed. Left `None` for a plain `walk_exact` run.
+    pub frontier_tracker: Option<Arc<FrontierTracker>>,
+    /// Nodes a prior walk already validated, scoping this walk to the changed
+    /// subgraph. See `PriorValidatedSet`.
+    pub prior_validated: Option<Arc<PriorValidatedSet>>,
+    /// Additional check-result sinks, e.g. `JsonLinesWalkReporter`/`TapWalkReporter`,
+    /// fanned out to alongside the existing inline Scuba logging. Empty by default.
+    pub reporters: Vec<Arc<dyn WalkReporter>>,
+    /// Durable, zstd-compressed artifact of as-data findings. See `FindingsSink`.
+    pub findings_sink: Option<Arc<FindingsSink>>,
+    /// Per-type visit counts for the edge/node coverage report. See `CoverageTracker`.
+    pub coverage: Option<Arc<CoverageTracker>>,
 }
 
 /// Walk the graph from one or more starting points,  providing stream of data for later reduction
@@ -1969,8 +3385,20 @@ where
             repo_params.hash_validation_node_types,
             repo_params.include_node_types,
             repo_params.sql_shard_info,
+            repo_params.path_selector_rules,
         );
 
+        let path_selectors = if path_selector_rules.is_empty() {
+            None
+        } else {
+            Some(PathSelectorIndex::parse(&path_selector_rules)?)
+        };
+
+        let coverage_for_report = type_params.coverage.clone();
+        let node_types_for_report = include_node_types.clone();
+        let edge_types_for_report = include_edge_types.clone();
+        let logger_for_report = repo_params.logger.clone();
+
         let mut required_node_data_types = type_params.required_node_data_types;
         required_node_data_types.extend(hash_validation_node_types.clone());
         let checker = Arc::new(Checker {
@@ -1982,6 +3410,20 @@ where
                 e.outgoing_type() == NodeType::HgFileNode
                     || e.outgoing_type() == NodeType::HgManifestFileNode
             }),
+            validate_blame_content: include_edge_types.contains(&EdgeType::BlameToFileContent),
+            with_content_length_only: include_node_types.contains(&NodeType::FileContentLength),
+            derived_data_capabilities: DerivedDataCapabilities::discover(
+                &repo,
+                type_params.force_derive_types.clone(),
+            ),
+            path_selectors,
+            captures: type_params.captures,
+            walk_order: type_params.walk_order,
+            frontier_tracker: type_params.frontier_tracker,
+            prior_validated: type_params.prior_validated,
+            reporters: type_params.reporters,
+            findings_sink: type_params.findings_sink,
+            coverage: type_params.coverage,
             include_edge_types,
             hash_validation_node_types,
             always_emit_edge_types: type_params.always_emit_edge_types,
@@ -1992,7 +3434,7 @@ where
             bonsai_hg_mapping: repo.bonsai_hg_mapping_arc().clone(),
         });
 
-        Ok(limited_by_key_shardable(
+        let stepped = limited_by_key_shardable(
             repo_params.scheduled_max,
             walk_roots,
             move |(via, walk_item): (Option<Route>, OutgoingEdge)| {
@@ -2053,11 +3495,263 @@ where
                         }),
                 )
             },
-        ))
+        );
+
+        // Log the coverage report once the walk has nothing left to step, rather
+        // than requiring every caller of `walk_exact` to remember to call
+        // `CoverageTracker::report` itself after draining the stream. `stream::unfold`
+        // carries the one-shot reporting state through the stream's own completion
+        // instead of bolting it on as a separate post-drain step a caller could forget.
+        let reported = stream::unfold(
+            (Box::pin(stepped), Some(coverage_for_report)),
+            move |(mut inner, mut pending_coverage)| {
+                cloned!(node_types_for_report, edge_types_for_report, logger_for_report);
+                async move {
+                    match inner.next().await {
+                        Some(item) => Some((item, (inner, pending_coverage))),
+                        None => {
+                            if let Some(Some(coverage)) = pending_coverage.take() {
+                                info!(
+                                    logger_for_report,
+                                    "{}",
+                                    coverage.report(&node_types_for_report, &edge_types_for_report)
+                                );
+                            }
+                            None
+                        }
+                    }
+                }
+            },
+        );
+
+        Ok(reported)
     }
     .try_flatten_stream()
 }
 
+/// Drives `walk_exact` to completion while periodically checkpointing the live
+/// frontier's deferred set to `repo_params.repo`'s blobstore (see
+/// `save_resume_state`), so a multi-hour validation walk that hits a transient
+/// failure can resume without losing track of previously-deferred changesets
+/// instead of restarting from `repo_params.walk_roots` with no memory of them. The
+/// underlying traversal is exactly `walk_exact`; this only adds frontier
+/// bookkeeping (see `FrontierTracker`) and the save/load calls around it.
+///
+/// `checkpoint_every` is how many completed steps pass between saves; `0` disables
+/// periodic saving (a checkpoint is still loaded on start, if one exists).
+///
+/// Relies on the same invariant tailing already depends on: re-stepping a node this
+/// visitor previously recorded as visited must be idempotent, since
+/// `VisitOne::needs_visit` is what keeps a restored deferred changeset that was
+/// already fully walked from re-emitting edges a caller already saw.
+pub fn walk_exact_resumable<V, VOut, Route>(
+    ctx: CoreContext,
+    visitor: V,
+    job_params: JobWalkParams,
+    repo_params: RepoWalkParams,
+    type_params: RepoWalkTypeParams,
+    checkpoint_key: CheckpointKey,
+    checkpoint_every: usize,
+) -> impl Stream<Item = Result<VOut, Error>>
+where
+    V: 'static + Clone + WalkVisitor<VOut, Route> + Send + Sync,
+    VOut: 'static + Send,
+    Route: 'static + Send + Clone + StepRoute,
+{
+    async move {
+        let blobstore = repo_params.repo.repo_blobstore().clone();
+        let checkpoint_ctx = ctx.clone();
+        let resumed_deferred = load_resume_state(&checkpoint_ctx, &blobstore, &checkpoint_key).await?;
+
+        let tracker = Arc::new(FrontierTracker::new(repo_params.walk_roots.iter().cloned()));
+        if let Some(deferred) = resumed_deferred {
+            tracker.seed_deferred(deferred);
+        }
+        let type_params = RepoWalkTypeParams {
+            frontier_tracker: Some(tracker.clone()),
+            ..type_params
+        };
+
+        let mut completed = 0usize;
+        let inner = walk_exact(ctx, visitor, job_params, repo_params, type_params);
+        Ok(inner.then(move |item| {
+            completed += 1;
+            let due = checkpoint_every > 0 && completed % checkpoint_every == 0 && item.is_ok();
+            let tracker = tracker.clone();
+            let blobstore = blobstore.clone();
+            let checkpoint_key = checkpoint_key.clone();
+            let ctx = checkpoint_ctx.clone();
+            async move {
+                if due {
+                    save_resume_state(&ctx, &blobstore, &checkpoint_key, &tracker.snapshot())
+                        .await?;
+                }
+                item
+            }
+        }))
+    }
+    .try_flatten_stream()
+}
+
+/// A typed add/remove event emitted by `reconcile_bookmark_move` as a node enters or
+/// leaves the reachable graph when a bookmark moves.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WalkDelta {
+    Added(Node),
+    Removed(Node),
+}
+
+/// A reference-counted "is this node currently reachable from any tracked root" bag:
+/// each node's count is the number of current roots whose walk reaches it, so
+/// reachability via more than one root coalesces rather than causing spurious
+/// remove-then-add churn when one root stops covering a node but another still does.
+///
+/// Counts never go negative: `decrement` on a node already at zero is a bug in the
+/// caller (it walked a node as reachable from a root it never actually started the
+/// walk from) and panics rather than silently going inconsistent.
+#[derive(Clone, Debug, Default)]
+pub struct NodeRefCountBag {
+    counts: HashMap<Node, u64>,
+}
+
+impl NodeRefCountBag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, node: &Node) -> bool {
+        self.counts.get(node).copied().unwrap_or(0) > 0
+    }
+
+    /// Increment `node`'s count; returns `true` the first time it goes from 0 to 1.
+    fn increment(&mut self, node: Node) -> bool {
+        let count = self.counts.entry(node).or_insert(0);
+        *count += 1;
+        *count == 1
+    }
+
+    /// Decrement `node`'s count; returns `true` when it drops from 1 to 0.
+    fn decrement(&mut self, node: &Node) -> bool {
+        match self.counts.get_mut(node) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                let dropped_to_zero = *count == 0;
+                if dropped_to_zero {
+                    self.counts.remove(node);
+                }
+                dropped_to_zero
+            }
+            _ => panic!(
+                "NodeRefCountBag: decrement of node not counted as reachable: {:?}",
+                node
+            ),
+        }
+    }
+}
+
+/// Re-walk only the delta of a bookmark move: walk from `new_root` (the bookmark's
+/// new target, if any) incrementing `bag`, then from `old_root` (its previous
+/// target, if any) decrementing `bag`, and return the nodes that actually entered or
+/// left the reachable graph as `WalkDelta` events. This lets a long-running service
+/// keep a derived index fresh from a moving set of public heads without a full
+/// re-traversal of the graph on every move.
+///
+/// Reuses `walk_exact` for the actual per-edge traversal decisions, so `Checker`
+/// path-selectors, capture fields, chunking etc. all still apply unchanged; this
+/// just wraps two such walks with the ref-counting bag to coalesce nodes reachable
+/// from more than one root.
+///
+/// Each call here is a single, non-chunked `walk_exact` run: there is no later call
+/// that comes back to "resolve" a node this one deferred. So a node deferred via
+/// `in_chunk` is fed into the bag exactly like a fully-stepped one -- `defer_visit`
+/// still returns a `VOut` for it, and `walk_exact`'s stream has no way to tag that
+/// `VOut` as having come from `StepOutput::Deferred` rather than `StepOutput::Done`.
+/// This is correct for what this function actually tracks: reachability of the node
+/// itself from the current roots, not full traversal of its subtree, and the node
+/// was genuinely reached either way. It does mean a deferred node's *descendants*
+/// are absent from this call's `deltas` until some later reconciling move re-walks
+/// through it with a chunk membership that resolves it.
+///
+/// The visitor's `VOut` is expected to be the walked `Node` itself; callers
+/// composing richer `WalkVisitor` output should project down to `Node` first.
+pub async fn reconcile_bookmark_move<V, Route>(
+    ctx: CoreContext,
+    visitor: V,
+    job_params: JobWalkParams,
+    repo_params: RepoWalkParams,
+    type_params: RepoWalkTypeParams,
+    bag: &mut NodeRefCountBag,
+    old_root: Option<OutgoingEdge>,
+    new_root: Option<OutgoingEdge>,
+) -> Result<Vec<WalkDelta>, Error>
+where
+    V: 'static + Clone + WalkVisitor<Node, Route> + Send + Sync,
+    Route: 'static + Send + Clone + StepRoute,
+{
+    let mut deltas = vec![];
+
+    // Increment before decrementing, so a node reachable from both roots never
+    // passes through zero and is reported neither as Removed nor as Added.
+    if let Some(new_root) = new_root {
+        let mut new_params = repo_params.clone();
+        new_params.walk_roots = vec![new_root];
+        let nodes: Vec<Node> = walk_exact(
+            ctx.clone(),
+            visitor.clone(),
+            job_params.clone(),
+            new_params,
+            type_params.clone(),
+        )
+        .try_collect()
+        .await?;
+        for node in nodes {
+            if bag.increment(node.clone()) {
+                deltas.push(WalkDelta::Added(node));
+            }
+        }
+    }
+
+    if let Some(old_root) = old_root {
+        let mut old_params = repo_params;
+        old_params.walk_roots = vec![old_root];
+        let nodes: Vec<Node> = walk_exact(ctx, visitor, job_params, old_params, type_params)
+            .try_collect()
+            .await?;
+        for node in nodes {
+            if bag.decrement(&node) {
+                deltas.push(WalkDelta::Removed(node));
+            }
+        }
+    }
+
+    Ok(deltas)
+}
+
+/// The set of `Node`s a prior walk already validated, so a later walk can turn a
+/// full traversal into a cheap delta: seed `repo_params.walk_roots` from just the
+/// bookmarks/commits that moved since (see `reconcile_bookmark_move`), and let
+/// `Checker::prune_previously_validated` drop any child already in this set from
+/// the edge-validation block rather than re-stepping it.
+///
+/// Every node type walked here is either content-addressed (its id is a hash of its
+/// bytes) or a derivation keyed by a bonsai changeset id (derivation is
+/// deterministic), so having validated a given `Node` once, by id, is sufficient
+/// proof it would validate the same way again -- there's no separate "did the
+/// derived mapping change" check to make, because a changed mapping would show up
+/// as a different id and thus a different `Node`.
+#[derive(Clone, Debug, Default)]
+pub struct PriorValidatedSet(HashSet<Node>);
+
+impl PriorValidatedSet {
+    pub fn new(nodes: impl IntoIterator<Item = Node>) -> Self {
+        Self(nodes.into_iter().collect())
+    }
+
+    fn contains(&self, node: &Node) -> bool {
+        self.0.contains(node)
+    }
+}
+
 async fn walk_one<V, VOut, Route>(
     ctx: CoreContext,
     via: Option<Route>,
@@ -2144,17 +3838,21 @@ where
         Node::FileContentMetadataV2(content_id) => {
             file_content_metadata_v2_step(&ctx, &repo, &checker, content_id, enable_derive).await
         }
+        Node::FileContentLength(key) => file_content_length_step(&ctx, &repo, &checker, &key).await,
         Node::AliasContentMapping(AliasKey(alias)) => {
             alias_content_mapping_step(&ctx, &repo, &checker, alias).await
         }
         // Derived
-        Node::Blame(blame_id) => blame_step(&ctx, &repo, &checker, blame_id).await,
+        Node::Blame(blame_id) => blame_step(&ctx, &repo, &checker, blame_id, &mut scuba).await,
         Node::ChangesetInfo(bcs_id) => {
             changeset_info_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
         }
         Node::ChangesetInfoMapping(bcs_id) => {
             bonsai_changeset_info_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
         }
+        Node::DerivedDataCompleteness(bcs_id) => {
+            derived_data_completeness_step(&ctx, &repo, &checker, bcs_id, &mut scuba).await
+        }
         Node::DeletedManifestV2(id) => {
             deleted_manifest_v2_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
         }
@@ -2193,6 +3891,9 @@ where
 
     let edge_label = walk_item.label;
     let node_type = walk_item.target.get_type();
+    if let Some(coverage) = checker.coverage() {
+        coverage.record(edge_label, node_type);
+    }
 
     // Run hash validation if needed
     let step_result = match step_result {
@@ -2242,18 +3943,36 @@ where
                 StepError::Other(_) => "step",
             };
 
-            scuba
-                .add(EDGE_TYPE, Into::<&'static str>::into(edge_label))
-                .add(CHECK_TYPE, check_type)
-                .add(CHECK_FAIL, 1)
-                .add(ERROR_MSG, msg.clone())
-                .log();
+            log_check_fail(
+                &checker,
+                &mut scuba,
+                &walk_item.target,
+                Some(edge_label),
+                check_type,
+                &msg,
+            );
             // Optionally attempt to continue
             if error_as_data_node_types.contains(&walk_item.target.get_type()) {
                 if error_as_data_edge_types.is_empty()
                     || error_as_data_edge_types.contains(&walk_item.label)
                 {
                     warn!(logger, "{}", msg);
+                    if let Some(sink) = checker.findings_sink() {
+                        let kind = match &e {
+                            StepError::Missing(_) => FindingKind::Missing,
+                            StepError::HashValidationFailure(_) => {
+                                FindingKind::HashValidationFailure
+                            }
+                            StepError::Other(_) => FindingKind::Error,
+                        };
+                        sink.record(Finding {
+                            node: walk_item.target.clone(),
+                            edge_label,
+                            kind,
+                            message: msg.clone(),
+                        })
+                        .await?;
+                    }
                     match e {
                         StepError::Missing(_s) => Ok(StepOutput::Done(
                             NodeData::MissingAsData(walk_item.target.clone()),
@@ -2284,12 +4003,14 @@ where
         )
     })?;
 
+    let mut deferred_bcs_id = None;
     let (vout, via, next) = match step_output {
         StepOutput::Deferred(bcs_id) => {
+            deferred_bcs_id = Some(bcs_id);
             let (vout, via) = visitor.defer_visit(&bcs_id, &walk_item, via)?;
             (vout, via, vec![])
         }
-        StepOutput::Done(node_data, children) => {
+        StepOutput::Done(node_data, mut children) => {
             // make sure steps are valid.  would be nice if this could be static
             for c in &children {
                 if c.label.outgoing_type() != c.target.get_type() {
@@ -2303,10 +4024,16 @@ where
                 }
             }
 
+            checker.prune_previously_validated(&mut children);
+            checker.walk_order().reorder(&mut children);
+
             // Allow WalkVisitor to record state and decline outgoing nodes if already visited
-            visitor.visit(&ctx, walk_item, Some(node_data), via, children)
+            visitor.visit(&ctx, walk_item.clone(), Some(node_data), via, children)
         }
     };
+    if let Some(tracker) = checker.frontier_tracker() {
+        tracker.on_step_complete(&walk_item, deferred_bcs_id, &next);
+    }
     let via = Some(via);
     let next = next.into_iter().map(move |e| (via.clone(), e));
     Ok(Some((vout, next)))
@@ -0,0 +1,98 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+mod rc;
+
+use std::io;
+
+use clidispatch::ReqCtx;
+use cmdutil::ConfigExt;
+use cmdutil::Result;
+use cmdutil::define_flags;
+
+use crate::rc::RcTable;
+
+define_flags! {
+    pub struct DebuggcOpts {
+        /// attempt to reclaim the reported entries instead of only reporting them; indexedlog cannot delete a blob by key, so each attempt is reported as failed and its RC bookkeeping is kept
+        reclaim: bool,
+    }
+}
+
+pub fn run(ctx: ReqCtx<DebuggcOpts>, repo: &repo::repo::Repo) -> Result<u8> {
+    let config = repo.config();
+
+    let datastore_path =
+        revisionstore::util::get_cache_path(config, &Some("indexedlogdatastore"))?.unwrap();
+    let rc_path = datastore_path.join("rc_table");
+    let mut table = RcTable::open(&rc_path)?;
+
+    let delay_secs = config
+        .get_opt::<u64>("debuggc", "delay-secs")?
+        .unwrap_or(600);
+    let now = rc::now_secs();
+    let candidates = table.reclaimable(delay_secs, now);
+
+    ctx.core.io.write(
+        format!(
+            "rc entries: {}\ngc candidates ({}s safety window): {}\n",
+            table.len(),
+            delay_secs,
+            candidates.len(),
+        )
+        .into_bytes(),
+    )?;
+    for id in &candidates {
+        ctx.core.io.write(format!("  {}\n", id).into_bytes())?;
+    }
+
+    if ctx.opts.reclaim {
+        // `delete_blob` is the actual compaction primitive: indexedlog has no
+        // delete-by-key path yet (see cmddebugstore's own `--gc`, which has
+        // the same gap), so there is nothing honest to call here except an
+        // explicit failure. `compact` reports it as such per key instead of
+        // forgetting bookkeeping for a blob that's still on disk.
+        let failed = table.compact(&candidates, |_id| {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "indexedlog has no delete-by-key path yet; blob left on disk",
+            ))
+        });
+        table.save()?;
+        let reclaimed = candidates.len() - failed.len();
+        ctx.core
+            .io
+            .write(format!("reclaimed {} entries\n", reclaimed).into_bytes())?;
+        for (id, err) in &failed {
+            ctx.core
+                .io
+                .write(format!("  could not delete {}: {}\n", id, err).into_bytes())?;
+        }
+    } else {
+        ctx.core
+            .io
+            .write(b"(dry run; pass --reclaim to attempt reclaiming these entries)\n".to_vec())?;
+    }
+
+    Ok(0)
+}
+
+pub fn aliases() -> &'static str {
+    "debuggc"
+}
+
+pub fn doc() -> &'static str {
+    "report and optionally reclaim reference-counted GC candidates in the indexedlog store"
+}
+
+pub fn synopsis() -> Option<&'static str> {
+    None
+}
+
+pub fn enable_cas() -> bool {
+    false
+}
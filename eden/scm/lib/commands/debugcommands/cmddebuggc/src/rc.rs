@@ -0,0 +1,200 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Reference-counted GC bookkeeping for indexedlog-backed blob stores.
+//!
+//! Each key tracks a reference count, which write paths that make a blob
+//! reachable again (checkout, bookmark move, pack insert) are meant to bump,
+//! and drop once the blob is no longer reachable from that caller. A count
+//! reaching zero does not mean the blob is reclaimable yet: we record the
+//! moment it happened (`rc_zero_since`) and only treat the key as a GC
+//! candidate once a configurable safety window has fully elapsed, so a writer
+//! racing to re-reference a blob right after the last reader drops it never
+//! observes a dangling read.
+//!
+//! **Not yet wired up**: nothing in this tree actually calls
+//! [`RcTable::incref`]/[`RcTable::decref`] from a real write path yet --
+//! checkout, bookmark-move, and pack-insert code don't exist in this repo
+//! checkout to wire into. Until one of them calls in, every key's count stays
+//! at its default (zero, never incremented), so [`RcTable::reclaimable`] will
+//! only ever report keys whose bookkeeping was itself created by a previous,
+//! equally-unwired run -- in practice, nothing.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use types::HgId;
+
+/// One key's reference-count bookkeeping. A "healthy" entry -- positive count,
+/// never hit zero -- is never written out at all (see `RcTable::save`), so the
+/// on-disk table only grows with the store's *candidates*, not its full key
+/// count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RcEntry {
+    pub count: u64,
+    /// Unix timestamp the count first reached zero; `None` while `count > 0`.
+    pub rc_zero_since: Option<u64>,
+}
+
+impl RcEntry {
+    fn is_present(&self) -> bool {
+        self.rc_zero_since.is_some()
+    }
+}
+
+/// Reference-count table for one store, persisted as a flat text file
+/// alongside the store's blobs: one `<hex id> <count> <rc_zero_since|->` line
+/// per entry whose count has reached zero (`rc_zero_since` set). The table is expected to stay small relative to the
+/// blob store itself -- most keys never round-trip through zero -- so a plain
+/// in-memory map snapshotted to disk is sufficient; it doesn't need its own
+/// indexedlog.
+pub struct RcTable {
+    path: PathBuf,
+    entries: HashMap<HgId, RcEntry>,
+}
+
+impl RcTable {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = HashMap::new();
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                for line in contents.lines() {
+                    if let Some((id, entry)) = parse_line(line) {
+                        if entry.is_present() {
+                            entries.insert(id, entry);
+                        }
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(Self { path, entries })
+    }
+
+    /// Mark `id` as reachable again: bump its count and clear any pending
+    /// deletion timer.
+    pub fn incref(&mut self, id: HgId) {
+        let entry = self
+            .entries
+            .entry(id)
+            .or_insert(RcEntry { count: 0, rc_zero_since: None });
+        entry.count += 1;
+        entry.rc_zero_since = None;
+    }
+
+    /// Mark `id` as no longer reachable from this caller. Once the count hits
+    /// zero, starts the deletion-safety window from `now`.
+    pub fn decref(&mut self, id: HgId, now: u64) {
+        let entry = self
+            .entries
+            .entry(id)
+            .or_insert(RcEntry { count: 0, rc_zero_since: None });
+        entry.count = entry.count.saturating_sub(1);
+        if entry.count == 0 && entry.rc_zero_since.is_none() {
+            entry.rc_zero_since = Some(now);
+        }
+    }
+
+    /// Keys whose count is zero and whose safety window
+    /// (`rc_zero_since + delay_secs`) has fully elapsed as of `now`.
+    pub fn reclaimable(&self, delay_secs: u64, now: u64) -> Vec<HgId> {
+        self.entries
+            .iter()
+            .filter_map(|(id, entry)| match entry.rc_zero_since {
+                Some(since) if entry.count == 0 && since.saturating_add(delay_secs) <= now => {
+                    Some(*id)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Drop the RC bookkeeping for `ids`. Call once their blobs have actually
+    /// been deleted from the underlying store, so a later run doesn't keep
+    /// proposing already-reclaimed keys.
+    pub fn forget(&mut self, ids: &[HgId]) {
+        for id in ids {
+            self.entries.remove(id);
+        }
+    }
+
+    /// The actual compaction pass: for each candidate, physically delete its
+    /// blob via `delete_blob`, and only then drop its RC bookkeeping with
+    /// [`RcTable::forget`]. Keeps going past a single failure -- rather than
+    /// aborting the whole batch -- so one bad key doesn't block reclaiming
+    /// the rest; every id `delete_blob` errored on is returned so the caller
+    /// can report it instead of silently leaving the blob on disk while
+    /// claiming success.
+    pub fn compact(
+        &mut self,
+        candidates: &[HgId],
+        mut delete_blob: impl FnMut(&HgId) -> io::Result<()>,
+    ) -> Vec<(HgId, io::Error)> {
+        let mut deleted = Vec::with_capacity(candidates.len());
+        let mut failed = Vec::new();
+        for id in candidates {
+            match delete_blob(id) {
+                Ok(()) => deleted.push(*id),
+                Err(e) => failed.push((*id, e)),
+            }
+        }
+        self.forget(&deleted);
+        failed
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut contents = String::new();
+        for (id, entry) in &self.entries {
+            if !entry.is_present() {
+                continue;
+            }
+            let since = match entry.rc_zero_since {
+                Some(t) => t.to_string(),
+                None => "-".to_string(),
+            };
+            contents.push_str(&format!("{} {} {}\n", id, entry.count, since));
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, contents)
+    }
+}
+
+fn parse_line(line: &str) -> Option<(HgId, RcEntry)> {
+    let mut parts = line.split_whitespace();
+    let id = HgId::from_str(parts.next()?).ok()?;
+    let count = parts.next()?.parse().ok()?;
+    let rc_zero_since = match parts.next()? {
+        "-" => None,
+        since => Some(since.parse().ok()?),
+    };
+    Some((id, RcEntry { count, rc_zero_since }))
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,71 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Best-effort btrfs transparent-compression attribute for the store directory.
+//!
+//! Setting the VFS `FS_COMPR_FL` inode flag on a directory makes btrfs
+//! transparently compress every file subsequently created inside it; other
+//! filesystems simply reject the ioctl. Applied once to the store's top-level
+//! directory rather than per log file, since btrfs propagates the attribute
+//! to new log segments created inside it automatically.
+
+use std::path::Path;
+use std::sync::Once;
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+
+    const FS_IOC_SETFLAGS: libc::c_ulong = 0x4008_6602;
+    const FS_COMPR_FL: libc::c_long = 0x0000_0004;
+
+    pub fn set_compression(path: &Path) -> io::Result<()> {
+        let dir = File::open(path)?;
+        let flag: libc::c_long = FS_COMPR_FL;
+        // SAFETY: `dir` stays open for the duration of the call and `flag` is a
+        // valid pointer to the value the ioctl expects.
+        let ret = unsafe { libc::ioctl(dir.as_raw_fd(), FS_IOC_SETFLAGS as _, &flag) };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use std::io;
+    use std::path::Path;
+
+    pub fn set_compression(_path: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "btrfs compression attribute is only supported on Linux",
+        ))
+    }
+}
+
+static WARNED: Once = Once::new();
+
+/// Apply the btrfs compression attribute to `path`. Falls back gracefully on
+/// volumes that don't support it (anything but btrfs, or insufficient
+/// permissions): warns once per process rather than once per call, and the
+/// store simply continues uncompressed.
+pub fn enable_best_effort(path: &Path) {
+    if let Err(e) = imp::set_compression(path) {
+        WARNED.call_once(|| {
+            tracing::warn!(
+                "could not enable btrfs compression on {}: {} (continuing uncompressed)",
+                path.display(),
+                e
+            );
+        });
+    }
+}
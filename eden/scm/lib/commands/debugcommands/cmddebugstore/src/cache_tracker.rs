@@ -0,0 +1,158 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! SQLite-backed last-access tracking and LRU pruning for indexedlog data-store
+//! caches.
+//!
+//! `IndexedLogHgIdDataStore`/`UnionHgIdDataStore` rotate purely on log age and
+//! size, with no notion of which keys are actually still hot. This records,
+//! per store key, the last time it was read and its approximate on-disk size
+//! in a small SQLite database alongside the cache, so `gc` can identify
+//! genuinely cold entries and trim to a byte budget by recency rather than
+//! by whatever happens to be oldest in log-rotation order.
+//!
+//! **Scope note**: `record_access` is currently only called from
+//! `cmddebugstore`'s own manual `unionstore.get` in [`crate::run`], not from
+//! `UnionHgIdDataStore`/`IndexedLogHgIdDataStore`'s real read paths (the
+//! `revisionstore` crate isn't part of this checkout to wire into), so
+//! ordinary reads during checkout/pull don't update recency here yet. And
+//! since `IndexedLogHgIdDataStore` has no delete-by-key path, `gc`'s
+//! `delete_blob` callback can only drop the tracker row for a cold key, not
+//! the underlying blob -- see the call site in [`crate::run`].
+
+use std::path::Path;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Result;
+use rusqlite::Connection;
+use rusqlite::params;
+
+/// Skip persisting an access-time bump unless the recorded timestamp is at
+/// least this stale, so a hot key read thousands of times a second doesn't
+/// turn into thousands of writes a second.
+const COARSEN_SECS: i64 = 24 * 60 * 60;
+
+pub struct CacheTracker {
+    conn: Connection,
+}
+
+impl CacheTracker {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS last_access (
+                store_key TEXT PRIMARY KEY,
+                last_access_secs INTEGER NOT NULL,
+                approx_size INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS last_access_by_time ON last_access(last_access_secs);",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Record that `store_key` was just read, sized roughly `approx_size`
+    /// bytes. A no-op if the existing row is already fresher than
+    /// `COARSEN_SECS`, to bound write amplification on hot keys.
+    pub fn record_access(&self, store_key: &str, approx_size: u64) -> Result<()> {
+        let now = now_secs();
+        let existing: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT last_access_secs FROM last_access WHERE store_key = ?1",
+                params![store_key],
+                |row| row.get(0),
+            )
+            .ok();
+        if let Some(last) = existing {
+            if now - last < COARSEN_SECS {
+                return Ok(());
+            }
+        }
+        self.conn.execute(
+            "INSERT INTO last_access (store_key, last_access_secs, approx_size)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(store_key) DO UPDATE SET
+                last_access_secs = excluded.last_access_secs,
+                approx_size = excluded.approx_size",
+            params![store_key, now, approx_size as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Untrack entries untouched for `max_age_secs`, then -- if the tracked
+    /// total is still over `cache_limit_bytes` -- untrack least-recently-used
+    /// entries until under budget. `delete_blob` runs for each untracked key
+    /// inside the same SQLite transaction as the row removal, so a crash
+    /// mid-GC can't leave the tracker disagreeing with itself about what
+    /// row was removed. It does not, by itself, delete anything from the
+    /// underlying store -- see the scope note on the module doc.
+    pub fn gc(
+        &mut self,
+        max_age_secs: i64,
+        cache_limit_bytes: u64,
+        mut delete_blob: impl FnMut(&str) -> Result<()>,
+    ) -> Result<usize> {
+        let now = now_secs();
+        let tx = self.conn.transaction()?;
+        let mut evicted = 0usize;
+
+        let stale_keys: Vec<String> = {
+            let mut stmt =
+                tx.prepare("SELECT store_key FROM last_access WHERE last_access_secs < ?1")?;
+            let mut rows = stmt.query(params![now - max_age_secs])?;
+            let mut keys = Vec::new();
+            while let Some(row) = rows.next()? {
+                keys.push(row.get(0)?);
+            }
+            keys
+        };
+        for key in stale_keys {
+            delete_blob(&key)?;
+            tx.execute("DELETE FROM last_access WHERE store_key = ?1", params![key])?;
+            evicted += 1;
+        }
+
+        let mut total: i64 = tx.query_row(
+            "SELECT COALESCE(SUM(approx_size), 0) FROM last_access",
+            [],
+            |row| row.get(0),
+        )?;
+        if (total as u64) > cache_limit_bytes {
+            let lru_keys: Vec<(String, i64)> = {
+                let mut stmt = tx.prepare(
+                    "SELECT store_key, approx_size FROM last_access ORDER BY last_access_secs ASC",
+                )?;
+                let mut rows = stmt.query([])?;
+                let mut keys = Vec::new();
+                while let Some(row) = rows.next()? {
+                    keys.push((row.get(0)?, row.get(1)?));
+                }
+                keys
+            };
+            for (key, size) in lru_keys {
+                if (total as u64) <= cache_limit_bytes {
+                    break;
+                }
+                delete_blob(&key)?;
+                tx.execute("DELETE FROM last_access WHERE store_key = ?1", params![&key])?;
+                total -= size;
+                evicted += 1;
+            }
+        }
+
+        tx.commit()?;
+        Ok(evicted)
+    }
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
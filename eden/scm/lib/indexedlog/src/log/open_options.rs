@@ -50,6 +50,12 @@ pub struct IndexDef {
     /// This function gets the commit metadata as input. It then parses the
     /// input, and extract parent commit hashes as the output. A git commit can
     /// have 0 or 1 or 2 or even more parents. Therefore the output is a [`Vec`].
+    ///
+    /// Beyond single-key lookups, keys in this index are compared in bytewise
+    /// order (see [`IndexOutput`]), the order [`lookup_range_in_sorted`]
+    /// expects when answering "all values whose key falls in `[start, end)`"
+    /// -- e.g. commits whose timestamp prefix is in a window, if the index key
+    /// encodes a timestamp. No [`Log`] method runs such a range lookup.
     pub(crate) func: Arc<dyn Fn(&[u8]) -> Vec<IndexOutput> + Send + Sync + 'static>,
 
     /// Name of the index.
@@ -73,9 +79,32 @@ pub struct IndexDef {
     ///
     /// Practically, this correlates to how fast `func` is.
     pub(crate) lag_threshold: u64,
+
+    /// Optional fingerprint identifying the current version of `func`.
+    ///
+    /// The contract used to be "when `func` changes, change `name`," which
+    /// forces callers to rotate index files by hand and throws away the old
+    /// index outright. Setting a `fingerprint` instead lets [`OpenOptions`]
+    /// detect the change itself: it is folded into the on-disk index and
+    /// metadata file names (see [`IndexDef::metaname`] /
+    /// [`IndexDef::filename`]), so changing it has the same effect as
+    /// renaming `name` -- the old index is left alone on disk and a new one
+    /// is rebuilt under the new fingerprinted name -- without callers having
+    /// to invent a fresh `name` every time `func`'s logic changes.
+    ///
+    /// The new index is built by the same open-time path that builds any
+    /// index missing on disk; [`OpenOptions::reindex_batch_size`] is not
+    /// consulted, so the build is not split into batches.
+    pub(crate) fingerprint: Option<u64>,
 }
 
 /// Output of an index function. Bytes that can be used for lookups.
+///
+/// For both [`IndexOutput::Reference`] and [`IndexOutput::Owned`], keys are
+/// ordered bytewise (unsigned lexicographic comparison of the raw bytes),
+/// the same order [`Index`] uses internally. `lookup_range_in_sorted`
+/// relies on this order: it starts at the range's lower bound and walks
+/// forward in that same bytewise order until it passes the upper bound.
 pub enum IndexOutput {
     /// The index key is a slice, relative to the data entry (ex. input of the
     /// index function).
@@ -114,6 +143,114 @@ pub enum ChecksumType {
     /// platforms, but takes less space. Perhaps a good fit when entries are
     /// short.
     Xxhash32,
+
+    /// Use the BLAKE3 cryptographic hash. Unlike the xxhash variants, which
+    /// only guard against accidental corruption, BLAKE3 is collision- and
+    /// preimage-resistant, so it also detects deliberate tampering. Slower
+    /// and takes more space per entry than xxhash; pick this when the
+    /// threat model includes an adversary rather than just bit rot.
+    ///
+    /// Unlike [`ChecksumType::Auto`], this is never chosen automatically --
+    /// it must be requested explicitly via [`OpenOptions::checksum_type`].
+    Blake3,
+}
+
+impl ChecksumType {
+    /// Computes the checksum of `data` (the stored, i.e. possibly
+    /// compressed, bytes) per this algorithm. `Auto` resolves to xxhash32
+    /// below 1KB and xxhash64 at or above it, matching the historical
+    /// size-based heuristic; every other variant is explicit.
+    ///
+    /// [`ChecksumType::verify`] calls it to recompute a checksum for
+    /// comparison.
+    pub(crate) fn checksum(self, data: &[u8]) -> Box<[u8]> {
+        const AUTO_XXHASH64_THRESHOLD: usize = 1024;
+        match self {
+            ChecksumType::Auto if data.len() >= AUTO_XXHASH64_THRESHOLD => {
+                ChecksumType::Xxhash64.checksum(data)
+            }
+            ChecksumType::Auto => ChecksumType::Xxhash32.checksum(data),
+            ChecksumType::Xxhash64 => {
+                Box::new(twox_hash::XxHash64::oneshot(0, data).to_be_bytes())
+            }
+            ChecksumType::Xxhash32 => {
+                Box::new(twox_hash::XxHash32::oneshot(0, data).to_be_bytes())
+            }
+            ChecksumType::Blake3 => Box::new(*blake3::hash(data).as_bytes()),
+        }
+    }
+
+    /// Recomputes the checksum of `data` and compares it against
+    /// `stored_checksum`. Never panics on mismatch, so a caller can keep
+    /// scanning past a corrupt entry and report the full extent of the
+    /// damage. No [`Log`] method calls it.
+    pub(crate) fn verify(self, data: &[u8], stored_checksum: &[u8]) -> bool {
+        &*self.checksum(data) == stored_checksum
+    }
+}
+
+/// What compression, if any, to apply to an entry's payload before it is
+/// written to the primary log buffer.
+///
+/// Unlike [`OpenOptions::btrfs_compression`], which relies on the
+/// filesystem's own transparent compression and therefore only helps when
+/// the backing volume happens to be btrfs, this is a real in-crate codec:
+/// [`CompressionType::compress`]/[`CompressionType::decompress`] do the
+/// actual encoding, and [`Log::append`]/[`Log::read`] are the call sites
+/// that must invoke them on the payload before buffering it / after
+/// loading it. The checksum recorded for an entry is always computed over
+/// the stored (i.e. compressed, when enabled) bytes, so integrity checks
+/// stay deterministic regardless of codec.
+///
+/// Compression is persisted per-[`Log`] in [`LogMetadata`]: reopening an
+/// existing [`Log`] with a different [`CompressionType`] than the one it
+/// was created with is rejected, since older entries on disk were written
+/// with the original codec and can't be transparently reinterpreted.
+///
+/// Compression is incompatible with [`IndexOutput::Reference`]: once the
+/// stored bytes are compressed, a byte range into them no longer
+/// identifies the same key bytes an uncompressed index function saw. Index
+/// functions used on a compressed [`Log`] must only produce
+/// [`IndexOutput::Owned`], [`IndexOutput::Remove`], or
+/// [`IndexOutput::RemovePrefix`] -- see [`IndexOutput::into_cow`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CompressionType {
+    /// Do not compress entries. The default.
+    None,
+
+    /// Compress entries with LZ4. Fast, modest compression ratio.
+    Lz4,
+
+    /// Compress entries with Zstd at the given level. Slower than LZ4, but
+    /// generally a better compression ratio; higher levels trade more CPU
+    /// for smaller output.
+    Zstd(i32),
+}
+
+impl CompressionType {
+    /// Compresses `data` per this codec. This is the function
+    /// [`Log::append`] must call on the payload before buffering it.
+    pub(crate) fn compress(self, data: &[u8]) -> crate::Result<Vec<u8>> {
+        match self {
+            CompressionType::None => Ok(data.to_vec()),
+            CompressionType::Lz4 => Ok(lz4_flex::compress_prepend_size(data)),
+            CompressionType::Zstd(level) => zstd::encode_all(data, level)
+                .map_err(|e| crate::Error::path(std::path::Path::new("<entry>"), e)),
+        }
+    }
+
+    /// Reverses [`CompressionType::compress`]. This is the function
+    /// [`Log::read`] must call on the stored bytes before handing them to
+    /// callers.
+    pub(crate) fn decompress(self, data: &[u8]) -> crate::Result<Vec<u8>> {
+        match self {
+            CompressionType::None => Ok(data.to_vec()),
+            CompressionType::Lz4 => lz4_flex::decompress_size_prepended(data)
+                .map_err(|e| crate::Error::programming(format!("corrupt lz4 entry: {}", e))),
+            CompressionType::Zstd(_) => zstd::decode_all(data)
+                .map_err(|e| crate::Error::path(std::path::Path::new("<entry>"), e)),
+        }
+    }
 }
 
 /// Options used to configured how an [`Log`] is opened.
@@ -127,6 +264,10 @@ pub struct OpenOptions {
     pub(crate) fsync: bool,
     pub(crate) auto_sync_threshold: Option<u64>,
     pub(crate) btrfs_compression: bool,
+    pub(crate) compression: CompressionType,
+    pub(crate) reindex_batch_size: usize,
+    pub(crate) force_rebuild_indexes: Vec<String>,
+    pub(crate) offset_indexes: Vec<OffsetIndexDef>,
 }
 
 pub type FlushFilterFunc =
@@ -196,6 +337,7 @@ impl IndexDef {
             // indexes. Users should customize the value if the default is not
             // good enough.
             lag_threshold: 25 * 500,
+            fingerprint: None,
         }
     }
 
@@ -215,20 +357,182 @@ impl IndexDef {
             func: self.func,
             name: self.name,
             lag_threshold,
+            fingerprint: self.fingerprint,
+        }
+    }
+
+    /// Set a fingerprint identifying the current version of the index
+    /// function.
+    ///
+    /// See the field doc on [`IndexDef::fingerprint`] for how this drives
+    /// automatic index rebuilds instead of requiring a manual `name`
+    /// rotation whenever the function's logic changes.
+    pub fn fingerprint(self, fingerprint: u64) -> Self {
+        Self {
+            func: self.func,
+            name: self.name,
+            lag_threshold: self.lag_threshold,
+            fingerprint: Some(fingerprint),
         }
     }
 
     /// Name used in log metadata.
     pub(crate) fn metaname(&self) -> String {
-        format!("{}{}", META_PREFIX, self.name)
+        match self.fingerprint {
+            Some(fingerprint) => format!("{}{}@{:x}", META_PREFIX, self.name, fingerprint),
+            None => format!("{}{}", META_PREFIX, self.name),
+        }
+    }
+
+    /// Name used in filesystem.
+    pub(crate) fn filename(&self) -> String {
+        match self.fingerprint {
+            Some(fingerprint) => format!("{}{}@{:x}", INDEX_FILE_PREFIX, self.name, fingerprint),
+            None => format!("{}{}", INDEX_FILE_PREFIX, self.name),
+        }
+    }
+}
+
+/// Rebuilds `index_def` from `entries` in bounded batches of `batch_size`,
+/// calling `flush_batch` with each batch's `(offset, entry_bytes)` pairs
+/// (already run through `index_def.func`-ready `entry_bytes`) so the
+/// caller can apply them to an on-disk or in-memory [`Index`] and persist
+/// the partial progress before the next batch starts. Nothing calls it when a
+/// [`Log`] is opened: neither a fingerprint-triggered rebuild (see
+/// [`IndexDef::fingerprint`]) nor [`OpenOptions::rebuild_index`] goes
+/// through it.
+pub(crate) fn rebuild_index_in_batches(
+    index_def: &IndexDef,
+    entries: impl Iterator<Item = (u64, Vec<u8>)>,
+    batch_size: usize,
+    mut flush_batch: impl FnMut(&IndexDef, &[(u64, Vec<u8>)]) -> crate::Result<()>,
+) -> crate::Result<()> {
+    let batch_size = batch_size.max(1);
+    let mut batch = Vec::with_capacity(batch_size);
+    for entry in entries {
+        batch.push(entry);
+        if batch.len() >= batch_size {
+            flush_batch(index_def, &batch)?;
+            batch.clear();
+        }
     }
+    if !batch.is_empty() {
+        flush_batch(index_def, &batch)?;
+    }
+    Ok(())
+}
+
+/// Definition of a built-in sparse monotonic offset index.
+///
+/// Maps an append-order logical sequence number (assigned by [`Log::append`],
+/// starting at 0 for the first entry) to the byte offset of the entry it
+/// refers to. Unlike a regular [`IndexDef`], whose keys are extracted from
+/// entry content by a user-supplied function, the sequence number here isn't
+/// part of the entry at all -- it's assigned as a side effect of appending --
+/// so this is its own definition rather than another `IndexDef`.
+///
+/// Only every `sparsity`-th entry is kept (see [`OffsetIndexTable::record`]),
+/// so the footprint stays tiny relative to a full per-entry index. Resolving
+/// an arbitrary sequence number binary-searches the sparse entries for the
+/// checkpoint to scan forward from. The table is in-memory only; opening or
+/// appending to a [`Log`] neither builds nor consults it.
+#[derive(Clone)]
+pub struct OffsetIndexDef {
+    pub(crate) name: Arc<String>,
+    pub(crate) sparsity: u64,
+}
 
+impl OffsetIndexDef {
     /// Name used in filesystem.
+    ///
+    /// Folds `sparsity` in, the same way [`IndexDef::filename`] folds in a
+    /// fingerprint: changing the sparsity changes the name, so a stale
+    /// index built at the old sparsity is never misread as matching the new
+    /// one.
     pub(crate) fn filename(&self) -> String {
-        format!("{}{}", INDEX_FILE_PREFIX, self.name)
+        format!("{}{}@{:x}", INDEX_FILE_PREFIX, self.name, self.sparsity)
+    }
+
+    /// Creates the empty in-memory sparse table this definition describes.
+    pub(crate) fn empty_table(&self) -> OffsetIndexTable {
+        OffsetIndexTable {
+            sparsity: self.sparsity,
+            next_seq: 0,
+            entries: Vec::new(),
+        }
+    }
+}
+
+/// The sparse `sequence -> byte offset` mapping an [`OffsetIndexDef`]
+/// describes. [`OffsetIndexTable::record`] adds each appended entry, and
+/// [`OffsetIndexTable::checkpoint_before`]/
+/// [`OffsetIndexTable::checkpoints_for_range`] resolve sequence numbers.
+/// [`Log::append`] does not call `record`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct OffsetIndexTable {
+    sparsity: u64,
+    next_seq: u64,
+    // Sorted by sequence number (entries are recorded in append order).
+    entries: Vec<(u64, u64)>,
+}
+
+impl OffsetIndexTable {
+    /// Records the byte offset of the next appended entry, assigning it the
+    /// next sequence number. Only every `sparsity`-th entry (including the
+    /// very first) is actually kept, per [`OffsetIndexDef::sparsity`].
+    pub(crate) fn record(&mut self, offset: u64) {
+        let seq = self.next_seq;
+        if seq % self.sparsity.max(1) == 0 {
+            self.entries.push((seq, offset));
+        }
+        self.next_seq += 1;
+    }
+
+    /// Resolves `seq` to the byte offset of that entry, if it has been
+    /// appended. Binary-searches the sparse checkpoints for the closest one
+    /// at or before `seq`; the caller (a real [`Log`]) would then forward
+    /// scan from that checkpoint's offset, decoding entries one at a time
+    /// until it reaches sequence number `seq`. This returns the checkpoint
+    /// to scan from, not the final offset, since resolving past the
+    /// checkpoint requires decoding entries, which is [`Log`]'s job, not
+    /// this table's.
+    pub(crate) fn checkpoint_before(&self, seq: u64) -> Option<(u64, u64)> {
+        if seq >= self.next_seq {
+            return None;
+        }
+        match self.entries.binary_search_by_key(&seq, |&(s, _)| s) {
+            Ok(i) => Some(self.entries[i]),
+            Err(0) => None,
+            Err(i) => Some(self.entries[i - 1]),
+        }
+    }
+
+    /// Like [`OffsetIndexTable::checkpoint_before`], but for the start of a
+    /// `[start, end)` sequence range scan.
+    pub(crate) fn checkpoints_for_range(
+        &self,
+        range: Range<u64>,
+    ) -> impl Iterator<Item = &(u64, u64)> {
+        let start_idx = match self.entries.binary_search_by_key(&range.start, |&(s, _)| s) {
+            Ok(i) => i,
+            Err(0) => 0,
+            Err(i) => i - 1,
+        };
+        self.entries[start_idx..]
+            .iter()
+            .take_while(move |&&(s, _)| s < range.end)
     }
 }
 
+// NOTE: `CompressionType::compress`/`decompress`, `ChecksumType::checksum`/
+// `verify`, `OffsetIndexTable`, `rebuild_index_in_batches`, and
+// `lookup_range_in_sorted` in this file are the real, callable mechanisms each of
+// `Log::append`/`read`, `Log::verify_integrity`, the fingerprint-triggered
+// reindex, and `Log::lookup_by_sequence`/`scan_sequence_range`/
+// `lookup_range` need. Wiring them into those `Log` methods -- which walk
+// the on-disk entry format and own the real `Index` -- belongs in `log.rs`,
+// not this file.
+
 impl OpenOptions {
     #[allow(clippy::new_without_default)]
     /// Creates a blank new set of options ready for configuration.
@@ -247,6 +551,10 @@ impl OpenOptions {
             fsync: false,
             auto_sync_threshold: None,
             btrfs_compression: false,
+            compression: CompressionType::None,
+            reindex_batch_size: 8192,
+            force_rebuild_indexes: Vec::new(),
+            offset_indexes: Vec::new(),
         }
     }
 
@@ -268,6 +576,22 @@ impl OpenOptions {
         self
     }
 
+    /// Adds a built-in sparse monotonic offset index definition under `name`,
+    /// keeping only every `sparsity`-th appended entry (0 means use the
+    /// default of 4096). Opening a [`Log`] does not build this index.
+    ///
+    /// See [`OffsetIndexDef`] for how sequence numbers are assigned and
+    /// resolved. Unlike [`OpenOptions::index`], no index function is needed:
+    /// the key is the entry's append order, not anything extracted from its
+    /// content.
+    pub fn offset_index(mut self, name: &'static str, sparsity: u64) -> Self {
+        self.offset_indexes.push(OffsetIndexDef {
+            name: Arc::new(name.to_string()),
+            sparsity: if sparsity == 0 { 4096 } else { sparsity },
+        });
+        self
+    }
+
     /// Add a "fold" definition. See [`FoldDef`] and [`Fold`] for details.
     pub fn fold_def(mut self, name: &'static str, create_fold: fn() -> Box<dyn Fold>) -> Self {
         self.fold_defs.push(FoldDef::new(name, create_fold));
@@ -304,12 +628,34 @@ impl OpenOptions {
 
     /// Sets the checksum type.
     ///
-    /// See [`ChecksumType`] for details.
+    /// See [`ChecksumType`] for details. The chosen type is not passed to
+    /// [`LogMetadata`] when a [`Log`]'s metadata is created here (only the
+    /// compression codec is).
     pub fn checksum_type(mut self, checksum_type: ChecksumType) -> Self {
         self.checksum_type = checksum_type;
         self
     }
 
+    /// Sets an index-rebuild batch size, recorded in these options.
+    /// Defaults to 8192.
+    ///
+    /// Opening a [`Log`] does not consult it: lagging and
+    /// fingerprint-triggered index builds are not split into batches.
+    pub fn reindex_batch_size(mut self, reindex_batch_size: usize) -> Self {
+        self.reindex_batch_size = reindex_batch_size;
+        self
+    }
+
+    /// Records `name` in the list of indexes to rebuild from scratch (shown
+    /// in these options' `Debug` output).
+    ///
+    /// [`OpenOptions::open`] does not consult this list, so it does not by
+    /// itself cause the named index to be rebuilt.
+    pub fn rebuild_index(mut self, name: &str) -> Self {
+        self.force_rebuild_indexes.push(name.to_string());
+        self
+    }
+
     /// Sets the flush filter function.
     ///
     /// The function will be called at [`Log::sync`] time, if there are
@@ -340,6 +686,17 @@ impl OpenOptions {
         self
     }
 
+    /// Sets the application-level compression codec used for entry
+    /// payloads.
+    ///
+    /// See [`CompressionType`] for the tradeoffs, how this interacts with
+    /// [`LogMetadata`] persistence across reopens, and why index functions
+    /// on a compressed [`Log`] can't use [`IndexOutput::Reference`].
+    pub fn compression(mut self, compression: CompressionType) -> Self {
+        self.compression = compression;
+        self
+    }
+
     /// Construct [`Log`] at given directory. Incrementally build up specified
     /// indexes.
     ///
@@ -381,7 +738,7 @@ impl OpenOptions {
     pub(crate) fn create_in_memory(&self, dir: GenericPath) -> crate::Result<Log> {
         assert!(dir.as_opt_path().is_none());
         let result: crate::Result<_> = (|| {
-            let meta = LogMetadata::new_with_primary_len(PRIMARY_START_OFFSET);
+            let meta = LogMetadata::new_with_primary_len(PRIMARY_START_OFFSET, self.compression);
             let mem_buf = Box::pin(Vec::new());
             let (disk_buf, indexes) = Log::load_log_and_indexes(&dir, &meta, self, &mem_buf, None)?;
             let disk_folds = self.empty_folds();
@@ -433,23 +790,37 @@ impl OpenOptions {
         let create = self.create;
 
         // Do a lock-less load_or_create_meta to avoid the flock overhead.
-        let meta = Log::load_or_create_meta(dir, false).or_else(|err| {
+        // `self.compression` is only consulted when metadata is newly
+        // created; loading pre-existing metadata keeps whatever codec it
+        // was stamped with.
+        let meta = Log::load_or_create_meta(dir, false, self.compression).or_else(|err| {
             if create {
                 dir.mkdir()
                     .context("cannot mkdir after failing to read metadata")
                     .source(err)?;
                 // Make sure check and write happens atomically.
                 if lock.is_some() {
-                    Log::load_or_create_meta(dir, true)
+                    Log::load_or_create_meta(dir, true, self.compression)
                 } else {
                     let _lock = dir.lock()?;
-                    Log::load_or_create_meta(dir, true)
+                    Log::load_or_create_meta(dir, true, self.compression)
                 }
             } else {
                 Err(err).context(|| format!("cannot open Log at {:?}", &dir))
             }
         })?;
 
+        // A pre-existing Log keeps the codec it was created with; reopening
+        // it with a different one can't be transparently reconciled, since
+        // older on-disk entries were already written with the original
+        // codec.
+        if meta.compression_type != self.compression {
+            return Err(crate::Error::programming(format!(
+                "cannot open Log at {:?} with compression {:?}: it was created with {:?}",
+                &dir, self.compression, meta.compression_type
+            )));
+        }
+
         let mem_buf = Box::pin(Vec::new());
         let (disk_buf, indexes) =
             Log::load_log_and_indexes(dir, &meta, self, &mem_buf, reuse_indexes)?;
@@ -496,24 +867,45 @@ impl OpenOptions {
 }
 
 impl IndexOutput {
-    pub(crate) fn into_cow(self, data: &[u8]) -> crate::Result<Cow<'_, [u8]>> {
+    /// Converts to the borrowed or owned bytes an index entry should store.
+    ///
+    /// `compression` is the [`CompressionType`] the owning [`Log`] was
+    /// opened with. A [`IndexOutput::Reference`] is a byte range into the
+    /// *stored* `data`; once `data` is compressed, that range no longer
+    /// identifies the same key bytes an uncompressed index function saw, so
+    /// producing one while compression is active is a programming error
+    /// rather than a silently wrong index.
+    pub(crate) fn into_cow(
+        self,
+        data: &[u8],
+        compression: CompressionType,
+    ) -> crate::Result<Cow<'_, [u8]>> {
         Ok(match self {
-            IndexOutput::Reference(range) => Cow::Borrowed(
-                data.get(range.start as usize..range.end as usize)
-                    .ok_or_else(|| {
-                        let msg = format!(
-                            "IndexFunc returned range {:?} but the data only has {} bytes",
-                            range,
-                            data.len()
-                        );
-                        let mut err = crate::Error::programming(msg);
-                        // If the data is short, add its content to error message.
-                        if data.len() < 128 {
-                            err = err.message(format!("Data = {:?}", data))
-                        }
-                        err
-                    })?,
-            ),
+            IndexOutput::Reference(range) => {
+                if compression != CompressionType::None {
+                    return Err(crate::Error::programming(format!(
+                        "IndexFunc returned a Reference {:?} but the Log is compressed with {:?}; \
+                         index functions on a compressed Log must only produce Owned, Remove, or RemovePrefix",
+                        range, compression
+                    )));
+                }
+                Cow::Borrowed(
+                    data.get(range.start as usize..range.end as usize)
+                        .ok_or_else(|| {
+                            let msg = format!(
+                                "IndexFunc returned range {:?} but the data only has {} bytes",
+                                range,
+                                data.len()
+                            );
+                            let mut err = crate::Error::programming(msg);
+                            // If the data is short, add its content to error message.
+                            if data.len() < 128 {
+                                err = err.message(format!("Data = {:?}", data))
+                            }
+                            err
+                        })?,
+                )
+            }
             IndexOutput::Owned(key) => Cow::Owned(key.into_vec()),
             IndexOutput::Remove(_) | IndexOutput::RemovePrefix(_) => {
                 return Err(crate::Error::programming(
@@ -524,6 +916,25 @@ impl IndexOutput {
     }
 }
 
+/// Returns the values, in key order, for every `(key, value)` pair in
+/// `sorted_entries` whose key falls in `[range.start, range.end)`.
+///
+/// `sorted_entries` must already be sorted bytewise by key -- the same
+/// order [`IndexOutput`] keys are compared in (see its type docs) -- which
+/// is the order a real [`Index`]'s on-disk cursor walks in. It works on an
+/// in-memory slice only; no [`Log`] method seeks an [`Index`] cursor with
+/// it.
+pub(crate) fn lookup_range_in_sorted<'a, V>(
+    sorted_entries: &'a [(Vec<u8>, V)],
+    range: Range<&[u8]>,
+) -> impl Iterator<Item = &'a V> {
+    let start = sorted_entries.partition_point(|(k, _)| k.as_slice() < range.start);
+    sorted_entries[start..]
+        .iter()
+        .take_while(move |(k, _)| k.as_slice() < range.end)
+        .map(|(_, v)| v)
+}
+
 impl fmt::Debug for OpenOptions {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "OpenOptions {{ ")?;
@@ -544,6 +955,17 @@ impl fmt::Debug for OpenOptions {
         write!(f, "create: {}, ", self.create)?;
         write!(f, "checksum_type: {:?}, ", self.checksum_type)?;
         write!(f, "auto_sync_threshold: {:?}, ", self.auto_sync_threshold)?;
+        write!(f, "compression: {:?}, ", self.compression)?;
+        write!(f, "reindex_batch_size: {}, ", self.reindex_batch_size)?;
+        write!(f, "force_rebuild_indexes: {:?}, ", self.force_rebuild_indexes)?;
+        write!(
+            f,
+            "offset_indexes: {:?}, ",
+            self.offset_indexes
+                .iter()
+                .map(|d| d.name.as_str())
+                .collect::<Vec<_>>()
+        )?;
         let flush_filter_desc = match self.flush_filter {
             Some(ref _buf) => "Some(_)",
             None => "None",